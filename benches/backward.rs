@@ -0,0 +1,47 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use neural_net_mnist::{
+    module::Module, multi_layer_perceptron::MultiLayerPerceptron, value::Value,
+};
+use std::hint::black_box;
+
+fn backward(c: &mut Criterion) {
+    let mut group = c.benchmark_group("backward");
+
+    // Graphs range from about 8k to 80k nodes
+    for hidden_size in [10, 50, 100] {
+        let model: MultiLayerPerceptron = MultiLayerPerceptron::new(784, &[hidden_size], 10);
+        let input = (0..784)
+            .map(|i| Value::new(i as f64 / 784.0))
+            .collect::<Vec<_>>();
+        let output = model.forward(&input);
+        let loss = output
+            .iter()
+            .fold(Value::new(0.0), |acc, o| &acc + &o.powf(2.0));
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(hidden_size),
+            &loss,
+            |b, loss| b.iter(|| black_box(loss.clone()).backward()),
+        );
+    }
+
+    group.finish();
+}
+
+fn backward_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("backward_chain");
+
+    for length in [1_000, 10_000, 100_000] {
+        let x = Value::new(0.5);
+        let root = (0..length).fold(x.clone(), |acc, _| (&acc * &x).tanh());
+
+        group.bench_with_input(BenchmarkId::from_parameter(length), &root, |b, root| {
+            b.iter(|| black_box(root.clone()).backward())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, backward, backward_chain);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use neural_net_mnist::{
+    module::Module,
+    multi_layer_perceptron::MultiLayerPerceptron,
+    value::{Value, no_grad},
+};
+use std::hint::black_box;
+
+fn forward(c: &mut Criterion) {
+    let mut group = c.benchmark_group("forward");
+
+    // The MNIST shape used by the examples
+    let model: MultiLayerPerceptron = MultiLayerPerceptron::new(784, &[50], 10);
+    let input = (0..784)
+        .map(|i| Value::new(i as f64 / 784.0))
+        .collect::<Vec<_>>();
+
+    group.bench_with_input(BenchmarkId::new("mlp", "784-50-10"), &input, |b, input| {
+        b.iter(|| model.forward(black_box(input)))
+    });
+    group.bench_with_input(
+        BenchmarkId::new("mlp_no_grad", "784-50-10"),
+        &input,
+        |b, input| b.iter(|| no_grad(|| model.forward(black_box(input)))),
+    );
+
+    group.finish();
+}
+
+criterion_group!(benches, forward);
+criterion_main!(benches);
@@ -0,0 +1,57 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use neural_net_mnist::{
+    mnist::{NUM_CLASSES, NUM_PIXELS, accuracy_function, loss_function},
+    multi_layer_perceptron::MultiLayerPerceptron,
+    training::{TrainingData, stochastic_gradient_descent},
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// Random MNIST-shaped examples, so the benchmark doesn't depend on the dataset being present.
+fn training_data(len: usize) -> Vec<TrainingData> {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..len)
+        .map(|_| {
+            let input = (0..NUM_PIXELS)
+                .map(|_| rng.random_range(0.0..1.0))
+                .collect();
+            let label = rng.random_range(0..NUM_CLASSES);
+            let expected_output = (0..NUM_CLASSES)
+                .map(|i| if i == label { 1.0 } else { 0.0 })
+                .collect();
+            TrainingData::new(input, expected_output)
+        })
+        .collect()
+}
+
+fn sgd_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sgd_step");
+    group.sample_size(10);
+
+    let model: MultiLayerPerceptron = MultiLayerPerceptron::new(NUM_PIXELS, &[40], NUM_CLASSES);
+    let data = training_data(100);
+
+    for batch_size in [1, 10] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(batch_size),
+            &batch_size,
+            |b, &batch_size| {
+                b.iter(|| {
+                    stochastic_gradient_descent(
+                        &model,
+                        &data,
+                        batch_size,
+                        0,
+                        loss_function,
+                        accuracy_function,
+                        |_| 0.01,
+                    )
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, sgd_step);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use neural_net_mnist::value::Value;
+use std::hint::black_box;
+
+const NUM_OPS: u64 = 1000;
+
+fn value_ops(c: &mut Criterion) {
+    let mut group = c.benchmark_group("value_ops");
+    group.throughput(Throughput::Elements(NUM_OPS));
+
+    let x = Value::new(0.5);
+    group.bench_function("add", |b| {
+        b.iter(|| (0..NUM_OPS).fold(Value::new(0.0), |acc, _| &acc + black_box(&x)))
+    });
+    group.bench_function("mul", |b| {
+        b.iter(|| (0..NUM_OPS).fold(Value::new(1.0), |acc, _| &acc * black_box(&x)))
+    });
+    group.bench_function("powf", |b| {
+        b.iter(|| (0..NUM_OPS).fold(Value::new(1.0), |acc, _| acc.powf(black_box(1.0))))
+    });
+    group.bench_function("tanh", |b| {
+        b.iter(|| (0..NUM_OPS).fold(Value::new(1.0), |acc, _| acc.tanh()))
+    });
+    group.bench_function("relu", |b| {
+        b.iter(|| (0..NUM_OPS).fold(Value::new(1.0), |acc, _| acc.relu()))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, value_ops);
+criterion_main!(benches);
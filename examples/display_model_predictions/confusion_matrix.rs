@@ -0,0 +1,88 @@
+use iced::{
+    Background, Color, Element, Length,
+    widget::{button, column, row, text},
+};
+
+use crate::{Message, dataset::Dataset};
+
+const CELL_SIZE: u16 = 56;
+
+/// Shows the confusion matrix of `dataset` with true labels as rows and predicted classes as
+/// columns, together with the accuracy and per-class precision and recall. Clicking a cell opens
+/// the examples of that pair.
+pub fn view(dataset: &Dataset) -> Element<'_, Message> {
+    let matrix = &dataset.confusion_matrix;
+    let total = dataset.data.len();
+    let num_correct = (0..10).map(|i| matrix[i][i]).sum::<usize>();
+    let max_off_diagonal = (0..10)
+        .flat_map(|label| (0..10).map(move |predicted| (label, predicted)))
+        .filter(|(label, predicted)| label != predicted)
+        .map(|(label, predicted)| matrix[label][predicted])
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let header = (0..10).fold(row![text("").width(CELL_SIZE)], |row, predicted| {
+        row.push(text(format!("P{predicted}")).width(CELL_SIZE).center())
+    });
+
+    let rows = (0..10).fold(column![header].spacing(2), |column, label| {
+        let cells = (0..10).fold(
+            row![text(format!("L{label}")).width(CELL_SIZE)].spacing(2),
+            |row, predicted| {
+                let count = matrix[label][predicted];
+                let color = if label == predicted {
+                    Color::from_rgb(0.2, 0.5, 0.2)
+                } else {
+                    // Errors get redder the more common they are compared to the worst one
+                    let intensity = count as f32 / max_off_diagonal as f32;
+                    Color::from_rgb(0.2 + 0.7 * intensity, 0.2, 0.2)
+                };
+
+                let cell = button(text(count).center().width(Length::Fill))
+                    .width(CELL_SIZE)
+                    .style(move |_, _| button::Style {
+                        background: Some(Background::Color(color)),
+                        text_color: Color::WHITE,
+                        ..button::Style::default()
+                    });
+                row.push(
+                    cell.on_press_maybe((count > 0).then_some(Message::ShowPair {
+                        label: label as u8,
+                        predicted: predicted as u8,
+                    })),
+                )
+            },
+        );
+        column.push(cells)
+    });
+
+    let metrics = (0..10).fold(
+        column![
+            text(format!(
+                "Accuracy: {:.2}% ({num_correct} of {total})",
+                num_correct as f64 / total as f64 * 100.0
+            )),
+            text("Class  Precision  Recall"),
+        ]
+        .spacing(2),
+        |column, class| {
+            let predicted = (0..10).map(|label| matrix[label][class]).sum::<usize>();
+            let actual = matrix[class].iter().sum::<usize>();
+            let ratio = |n: usize, d: usize| {
+                if d == 0 {
+                    0.0
+                } else {
+                    n as f64 / d as f64 * 100.0
+                }
+            };
+            column.push(text(format!(
+                "{class:>5}  {:>8.2}%  {:>5.2}%",
+                ratio(matrix[class][class], predicted),
+                ratio(matrix[class][class], actual)
+            )))
+        },
+    );
+
+    row![rows, metrics].spacing(20).into()
+}
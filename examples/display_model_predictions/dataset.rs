@@ -0,0 +1,195 @@
+use neural_net_mnist::{
+    mnist::{self, argmax, softmax},
+    model_file::{read_model, write_model},
+    multi_layer_perceptron::MultiLayerPerceptron,
+};
+use std::fmt;
+
+pub struct Data {
+    pub label: u8,
+    pub image_data: Vec<u8>,
+}
+
+impl Data {
+    pub fn input(&self) -> Vec<f64> {
+        self.image_data.iter().map(|&p| p as f64 / 255.0).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetKind {
+    Train,
+    Test,
+}
+
+impl DatasetKind {
+    fn file_path(self) -> &'static str {
+        match self {
+            DatasetKind::Train => "mnist_train.csv",
+            DatasetKind::Test => "mnist_test.csv",
+        }
+    }
+}
+
+/// Loads the dataset with `mnist::load_data`, keeping the pixels as bytes to save memory.
+fn load_data(kind: DatasetKind) -> Vec<Data> {
+    mnist::load_data(kind.file_path())
+        .expect(
+            "Failed to load dataset, \
+            download it from https://www.kaggle.com/datasets/oddrationale/mnist-in-csv",
+        )
+        .into_iter()
+        .map(|data| Data {
+            label: argmax(&data.expected_output) as u8,
+            image_data: data
+                .input
+                .iter()
+                .map(|&p| (p * 255.0).round() as u8)
+                .collect(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Prediction {
+    pub class: u8,
+    /// Softmax probability of the predicted class.
+    pub confidence: f64,
+}
+
+impl Prediction {
+    pub fn from_probabilities(probabilities: &[f64]) -> Self {
+        let (class, &confidence) = probabilities
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap();
+
+        Prediction {
+            class: class as u8,
+            confidence,
+        }
+    }
+}
+
+/// A dataset together with the model's prediction for every example, so it can be filtered.
+pub struct Dataset {
+    pub kind: DatasetKind,
+    pub data: Vec<Data>,
+    pub predictions: Vec<Prediction>,
+    /// Number of examples per (true label, predicted class) pair.
+    pub confusion_matrix: [[usize; 10]; 10],
+}
+
+impl Dataset {
+    pub fn load(kind: DatasetKind, model: &MultiLayerPerceptron) -> Self {
+        let data = load_data(kind);
+        let predictions = predict_all(model, &data);
+
+        let mut confusion_matrix = [[0; 10]; 10];
+        for (data, prediction) in data.iter().zip(&predictions) {
+            confusion_matrix[data.label as usize][prediction.class as usize] += 1;
+        }
+
+        Self {
+            kind,
+            data,
+            predictions,
+            confusion_matrix,
+        }
+    }
+
+    /// Indices of the examples that pass `filter`, optionally sorted by increasing confidence.
+    pub fn filtered_indices(&self, filter: Filter, sort_by_confidence: bool) -> Vec<usize> {
+        let mut indices = (0..self.data.len())
+            .filter(|&i| filter.matches(self.data[i].label, self.predictions[i].class))
+            .collect::<Vec<_>>();
+
+        if sort_by_confidence {
+            indices.sort_by(|&a, &b| {
+                self.predictions[a]
+                    .confidence
+                    .total_cmp(&self.predictions[b].confidence)
+            });
+        }
+
+        indices
+    }
+}
+
+/// Predicts every example on all cores. A model can't be shared between threads, so every thread
+/// reads its own copy from the serialized parameters.
+fn predict_all(model: &MultiLayerPerceptron, data: &[Data]) -> Vec<Prediction> {
+    let mut model_bytes = Vec::new();
+    write_model(model, &mut model_bytes).expect("Failed to serialize model");
+
+    let num_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = data.len().div_ceil(num_threads).max(1);
+    std::thread::scope(|scope| {
+        let handles = data
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let model_bytes = &model_bytes;
+                scope.spawn(move || {
+                    let model: MultiLayerPerceptron =
+                        read_model(model_bytes.as_slice()).expect("Failed to copy model");
+                    chunk
+                        .iter()
+                        .map(|data| {
+                            Prediction::from_probabilities(&softmax(&model.predict(&data.input())))
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    All,
+    Misclassified,
+    Label(u8),
+    Predicted(u8),
+    Pair { label: u8, predicted: u8 },
+}
+
+impl Filter {
+    pub fn all() -> Vec<Filter> {
+        [Filter::All, Filter::Misclassified]
+            .into_iter()
+            .chain((0..10).map(Filter::Label))
+            .chain((0..10).map(Filter::Predicted))
+            .collect()
+    }
+
+    fn matches(self, label: u8, prediction: u8) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Misclassified => label != prediction,
+            Filter::Label(class) => label == class,
+            Filter::Predicted(class) => prediction == class,
+            Filter::Pair {
+                label: pair_label,
+                predicted,
+            } => label == pair_label && prediction == predicted,
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::All => write!(f, "All"),
+            Filter::Misclassified => write!(f, "Misclassified"),
+            Filter::Label(class) => write!(f, "Label {class}"),
+            Filter::Predicted(class) => write!(f, "Predicted {class}"),
+            Filter::Pair { label, predicted } => write!(f, "Label {label}, predicted {predicted}"),
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use iced::{
+    Color, Point, Rectangle, Renderer, Theme, mouse,
+    widget::canvas::{self, Frame, Geometry, Path, Stroke, event},
+};
+
+use crate::{HEIGHT, WIDTH};
+
+/// Side length of the box the digit is scaled into, MNIST digits are normalized to 20x20 pixels
+/// and then centered in the 28x28 image by their center of mass.
+const DIGIT_SIZE: f32 = 20.0;
+
+/// Subsamples per pixel along each axis, for anti-aliasing.
+const SUBSAMPLES: usize = 4;
+
+/// Hand-drawn strokes in coordinates relative to the canvas, i.e. within [0, 1].
+pub struct Drawing {
+    strokes: Vec<Vec<Point>>,
+    /// Brush diameter relative to the canvas size.
+    pub thickness: f32,
+}
+
+impl Drawing {
+    pub fn new(thickness: f32) -> Self {
+        Self {
+            strokes: Vec::new(),
+            thickness,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.strokes.clear();
+    }
+
+    pub fn start_stroke(&mut self, point: Point) {
+        self.strokes.push(vec![point]);
+    }
+
+    pub fn extend_stroke(&mut self, point: Point) {
+        if let Some(stroke) = self.strokes.last_mut() {
+            stroke.push(point);
+        }
+    }
+
+    /// Rasterizes the drawing the way MNIST digits were prepared: the bounding box of the strokes
+    /// is scaled to fit into 20x20 pixels keeping the aspect ratio, and the result is centered in
+    /// the 28x28 image by its center of mass. Returns pixel intensities in [0, 1].
+    pub fn to_input(&self) -> Vec<f64> {
+        let mut pixels = vec![0.0; (WIDTH * HEIGHT) as usize];
+        let radius = self.thickness / 2.0;
+
+        let Some((min, max)) = self.bounding_box() else {
+            return pixels;
+        };
+        let (min, max) = (
+            Point::new(min.x - radius, min.y - radius),
+            Point::new(max.x + radius, max.y + radius),
+        );
+        let scale = DIGIT_SIZE / (max.x - min.x).max(max.y - min.y);
+        let offset = Point::new(
+            (WIDTH as f32 - (max.x - min.x) * scale) / 2.0,
+            (HEIGHT as f32 - (max.y - min.y) * scale) / 2.0,
+        );
+
+        for y in 0..HEIGHT as usize {
+            for x in 0..WIDTH as usize {
+                let mut covered = 0;
+                for sy in 0..SUBSAMPLES {
+                    for sx in 0..SUBSAMPLES {
+                        let pixel = Point::new(
+                            x as f32 + (sx as f32 + 0.5) / SUBSAMPLES as f32,
+                            y as f32 + (sy as f32 + 0.5) / SUBSAMPLES as f32,
+                        );
+                        let point = Point::new(
+                            min.x + (pixel.x - offset.x) / scale,
+                            min.y + (pixel.y - offset.y) / scale,
+                        );
+                        if self.distance(point) <= radius {
+                            covered += 1;
+                        }
+                    }
+                }
+                pixels[y * WIDTH as usize + x] = covered as f64 / (SUBSAMPLES * SUBSAMPLES) as f64;
+            }
+        }
+
+        center_by_mass(&pixels)
+    }
+
+    fn bounding_box(&self) -> Option<(Point, Point)> {
+        self.strokes.iter().flatten().fold(None, |bounds, p| {
+            let (min, max) = bounds.unwrap_or((*p, *p));
+            Some((
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            ))
+        })
+    }
+
+    /// Distance from `point` to the nearest stroke.
+    fn distance(&self, point: Point) -> f32 {
+        self.strokes
+            .iter()
+            .flat_map(|stroke| {
+                // A single point is drawn as a dot
+                let segments = stroke.windows(2).map(|w| (w[0], w[1]));
+                let dot = (stroke.len() == 1).then(|| (stroke[0], stroke[0]));
+                segments.chain(dot)
+            })
+            .map(|(a, b)| segment_distance(point, a, b))
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+fn segment_distance(p: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(Point::new(a.x + t * dx, a.y + t * dy))
+}
+
+/// Shifts the image by whole pixels so its center of mass is in the middle.
+fn center_by_mass(pixels: &[f64]) -> Vec<f64> {
+    let (width, height) = (WIDTH as i64, HEIGHT as i64);
+    let (mut total, mut sum_x, mut sum_y) = (0.0, 0.0, 0.0);
+    for (i, &p) in pixels.iter().enumerate() {
+        total += p;
+        sum_x += p * (i as i64 % width) as f64;
+        sum_y += p * (i as i64 / width) as f64;
+    }
+    if total == 0.0 {
+        return pixels.to_vec();
+    }
+
+    let shift_x = ((width - 1) as f64 / 2.0 - sum_x / total).round() as i64;
+    let shift_y = ((height - 1) as f64 / 2.0 - sum_y / total).round() as i64;
+
+    let mut centered = vec![0.0; pixels.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let (source_x, source_y) = (x - shift_x, y - shift_y);
+            if (0..width).contains(&source_x) && (0..height).contains(&source_y) {
+                centered[(y * width + x) as usize] = pixels[(source_y * width + source_x) as usize];
+            }
+        }
+    }
+    centered
+}
+
+/// A canvas that draws `Drawing` and reports mouse strokes as messages.
+pub struct DrawingCanvas<'a, Message> {
+    pub drawing: &'a Drawing,
+    pub on_stroke_start: fn(Point) -> Message,
+    pub on_stroke_extend: fn(Point) -> Message,
+    pub on_stroke_end: Message,
+}
+
+impl<Message: Clone> canvas::Program<Message> for DrawingCanvas<'_, Message> {
+    /// Whether the left mouse button is held down.
+    type State = bool;
+
+    fn update(
+        &self,
+        is_drawing: &mut bool,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let position = cursor
+            .position_in(bounds)
+            .map(|p| Point::new(p.x / bounds.width, p.y / bounds.height));
+
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(position) = position else {
+                    return (event::Status::Ignored, None);
+                };
+                *is_drawing = true;
+                (
+                    event::Status::Captured,
+                    Some((self.on_stroke_start)(position)),
+                )
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) if *is_drawing => match position
+            {
+                Some(position) => (
+                    event::Status::Captured,
+                    Some((self.on_stroke_extend)(position)),
+                ),
+                None => (event::Status::Captured, None),
+            },
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if *is_drawing =>
+            {
+                *is_drawing = false;
+                (event::Status::Captured, Some(self.on_stroke_end.clone()))
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        _is_drawing: &bool,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::BLACK);
+
+        let to_canvas = |p: &Point| Point::new(p.x * bounds.width, p.y * bounds.height);
+        let width = self.drawing.thickness * bounds.width;
+        for stroke in &self.drawing.strokes {
+            if let [point] = stroke.as_slice() {
+                frame.fill(&Path::circle(to_canvas(point), width / 2.0), Color::WHITE);
+                continue;
+            }
+            let path = Path::new(|builder| {
+                for (i, point) in stroke.iter().enumerate() {
+                    if i == 0 {
+                        builder.move_to(to_canvas(point));
+                    } else {
+                        builder.line_to(to_canvas(point));
+                    }
+                }
+            });
+            frame.stroke(
+                &path,
+                Stroke::default()
+                    .with_color(Color::WHITE)
+                    .with_width(width)
+                    .with_line_cap(canvas::LineCap::Round)
+                    .with_line_join(canvas::LineJoin::Round),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        _is_drawing: &bool,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
@@ -0,0 +1,571 @@
+use dataset::{Dataset, DatasetKind, Filter};
+use drawing::{Drawing, DrawingCanvas};
+use iced::{
+    Element, Font, Point,
+    widget::{
+        button, canvas, checkbox, column, pick_list, progress_bar, row, scrollable, slider, text,
+        text_input,
+    },
+};
+use neural_net_mnist::{
+    mnist::{NUM_CLASSES, NUM_PIXELS, argmax, softmax},
+    model_file::read_model_or_parameters,
+    multi_layer_perceptron::MultiLayerPerceptron,
+    saliency::{gradient_times_input, integrated_gradients, smooth_grad, vanilla_gradient},
+    weight_map::{WeightMap, first_layer_weight_maps},
+};
+use std::fs::File;
+
+mod confusion_matrix;
+mod dataset;
+mod drawing;
+
+const WIDTH: u32 = 28;
+const HEIGHT: u32 = 28;
+const FILTERS_DIR: &str = "filters";
+const FILTERS_PER_ROW: usize = 10;
+
+fn load_model_from_file(file: File) -> MultiLayerPerceptron {
+    // Older files without a header hold the parameters of the model trained by `train_model`
+    read_model_or_parameters(file, || {
+        MultiLayerPerceptron::new(NUM_PIXELS, &[50], NUM_CLASSES)
+    })
+    .expect("Failed to read model file")
+}
+
+/// Renders a saliency map with a diverging colormap: red for positive, blue for negative values,
+/// scaled so the largest magnitude is fully saturated.
+fn saliency_image_data(saliency: &[f64]) -> Vec<u8> {
+    let max = saliency.iter().fold(0.0f64, |max, s| max.max(s.abs()));
+    let scale = if max > 0.0 { 1.0 / max } else { 0.0 };
+
+    saliency
+        .iter()
+        .flat_map(|s| {
+            let intensity = ((s * scale).abs() * 255.0) as u8;
+            let fade = 255 - intensity;
+            if *s >= 0.0 {
+                [255, fade, fade, 255]
+            } else {
+                [fade, fade, 255, 255]
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SaliencyMethod {
+    VanillaGradient,
+    GradientTimesInput,
+    SmoothGrad,
+    IntegratedGradients,
+}
+
+impl SaliencyMethod {
+    const ALL: [SaliencyMethod; 4] = [
+        SaliencyMethod::VanillaGradient,
+        SaliencyMethod::GradientTimesInput,
+        SaliencyMethod::SmoothGrad,
+        SaliencyMethod::IntegratedGradients,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SaliencyMethod::VanillaGradient => "Gradient",
+            SaliencyMethod::GradientTimesInput => "Gradient x Input",
+            SaliencyMethod::SmoothGrad => "SmoothGrad",
+            SaliencyMethod::IntegratedGradients => "Integrated Gradients",
+        }
+    }
+
+    fn compute(self, model: &MultiLayerPerceptron, input: &[f64], class: usize) -> Vec<f64> {
+        match self {
+            SaliencyMethod::VanillaGradient => vanilla_gradient(model, input, class),
+            SaliencyMethod::GradientTimesInput => gradient_times_input(model, input, class),
+            SaliencyMethod::SmoothGrad => {
+                smooth_grad(model, input, class, 16, 0.15, &mut rand::rng())
+            }
+            SaliencyMethod::IntegratedGradients => {
+                integrated_gradients(model, input, &[0.0; NUM_PIXELS], class, 32)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Dataset,
+    ConfusionMatrix,
+    Filters,
+    Draw,
+}
+
+struct Viewer {
+    model: MultiLayerPerceptron,
+    dataset: Dataset,
+    /// The other dataset, kept after switching so it doesn't need to be loaded again.
+    cached_dataset: Option<Dataset>,
+    filter: Filter,
+    sort_by_confidence: bool,
+    /// Indices into the dataset of the examples that pass the filter.
+    indices: Vec<usize>,
+    position: usize,
+    jump_input: String,
+    mode: Mode,
+    drawing: Drawing,
+    input: Vec<f64>,
+    probabilities: Vec<f64>,
+    prediction: u8,
+    /// `None` if no saliency map is shown.
+    saliency_method: Option<SaliencyMethod>,
+    saliency: Vec<f64>,
+    weight_maps: Vec<WeightMap>,
+    export_status: String,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Next,
+    Previous,
+    SelectSaliencyMethod(Option<SaliencyMethod>),
+    SelectMode(Mode),
+    StrokeStarted(Point),
+    StrokeExtended(Point),
+    StrokeEnded,
+    ClearDrawing,
+    BrushThicknessChanged(f32),
+    SelectDataset(DatasetKind),
+    SelectFilter(Filter),
+    SortByConfidence(bool),
+    JumpInputChanged(String),
+    Jump,
+    ShowPair { label: u8, predicted: u8 },
+    ExportFilters,
+}
+
+impl Default for Viewer {
+    fn default() -> Self {
+        let model = load_model_from_file(File::open("model.bin").unwrap());
+        let dataset = Dataset::load(DatasetKind::Train, &model);
+        let indices = (0..dataset.data.len()).collect();
+        let weight_maps = first_layer_weight_maps(&model, WIDTH as usize, HEIGHT as usize)
+            .expect("First layer doesn't take 28x28 images");
+
+        let mut viewer = Self {
+            model,
+            dataset,
+            cached_dataset: None,
+            filter: Filter::All,
+            sort_by_confidence: false,
+            indices,
+            position: 0,
+            jump_input: String::new(),
+            mode: Mode::Dataset,
+            drawing: Drawing::new(0.08),
+            input: Vec::new(),
+            probabilities: Vec::new(),
+            prediction: 0,
+            saliency_method: Some(SaliencyMethod::VanillaGradient),
+            saliency: Vec::new(),
+            weight_maps,
+            export_status: String::new(),
+        };
+        viewer.refresh();
+        viewer
+    }
+}
+
+impl Viewer {
+    /// Recomputes the input, prediction and saliency map of the current example or drawing, which
+    /// is too expensive to do on every redraw.
+    fn refresh(&mut self) {
+        self.refresh_prediction();
+        self.refresh_saliency();
+    }
+
+    /// Recomputes only the input and prediction, which is cheap enough to follow a stroke while it
+    /// is being drawn.
+    fn refresh_prediction(&mut self) {
+        self.input = match (self.mode, self.data_index()) {
+            (Mode::Draw, _) => self.drawing.to_input(),
+            (_, Some(data_index)) => self.dataset.data[data_index].input(),
+            (_, None) => vec![0.0; (WIDTH * HEIGHT) as usize],
+        };
+
+        let output = self.model.predict(&self.input);
+        self.prediction = argmax(&output) as u8;
+        self.probabilities = softmax(&output);
+    }
+
+    fn refresh_saliency(&mut self) {
+        self.saliency = match self.saliency_method {
+            // Explain the predicted class, which is what matters for misclassifications
+            Some(method) => method.compute(&self.model, &self.input, self.prediction as usize),
+            None => vec![0.0; self.input.len()],
+        };
+    }
+
+    fn data_index(&self) -> Option<usize> {
+        self.indices.get(self.position).copied()
+    }
+
+    fn apply_filter(&mut self) {
+        self.indices = self
+            .dataset
+            .filtered_indices(self.filter, self.sort_by_confidence);
+        self.position = 0;
+    }
+
+    fn select_dataset(&mut self, kind: DatasetKind) {
+        if kind == self.dataset.kind {
+            return;
+        }
+        let dataset = match self.cached_dataset.take() {
+            Some(dataset) if dataset.kind == kind => dataset,
+            _ => Dataset::load(kind, &self.model),
+        };
+        self.cached_dataset = Some(std::mem::replace(&mut self.dataset, dataset));
+        self.apply_filter();
+    }
+
+    /// Jumps to the example with the index typed into the jump box, clearing the filter if it
+    /// hides that example.
+    fn jump(&mut self) {
+        let Ok(data_index) = self.jump_input.trim().parse::<usize>() else {
+            return;
+        };
+        if data_index >= self.dataset.data.len() {
+            return;
+        }
+        if !self.indices.contains(&data_index) {
+            self.filter = Filter::All;
+            self.sort_by_confidence = false;
+            self.apply_filter();
+        }
+        self.position = self.indices.iter().position(|&i| i == data_index).unwrap();
+    }
+
+    /// Writes every first-layer filter as a PNG and a PGM file.
+    fn export_filters(&self) -> neural_net_mnist::error::Result<()> {
+        std::fs::create_dir_all(FILTERS_DIR)?;
+        for (i, weight_map) in self.weight_maps.iter().enumerate() {
+            weight_map.write_png(File::create(format!("{FILTERS_DIR}/neuron_{i:03}.png"))?)?;
+            weight_map.write_pgm(File::create(format!("{FILTERS_DIR}/neuron_{i:03}.pgm"))?)?;
+        }
+        Ok(())
+    }
+
+    /// Shows the input weights of every first-layer neuron as an image.
+    fn filters_view(&self) -> Element<'_, Message> {
+        let filters = self.weight_maps.chunks(FILTERS_PER_ROW).enumerate().fold(
+            column![].spacing(10),
+            |column, (row_index, weight_maps)| {
+                column.push(weight_maps.iter().enumerate().fold(
+                    row![].spacing(10),
+                    |row, (i, weight_map)| {
+                        let pixels = weight_map
+                            .to_rgb()
+                            .chunks(3)
+                            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                            .collect::<Vec<_>>();
+                        row.push(column![
+                            iced::widget::image(iced::advanced::image::Handle::from_rgba(
+                                WIDTH, HEIGHT, pixels,
+                            ))
+                            .width((WIDTH * 3) as u16)
+                            .height((HEIGHT * 3) as u16),
+                            text(row_index * FILTERS_PER_ROW + i),
+                        ])
+                    },
+                ))
+            },
+        );
+
+        column![
+            row![
+                button("Export").on_press(Message::ExportFilters),
+                text(&self.export_status),
+            ]
+            .spacing(10),
+            scrollable(filters),
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn update(&mut self, message: Message) {
+        let len = self.indices.len().max(1);
+        match message {
+            Message::Next => self.position = (self.position + 1) % len,
+            Message::Previous => self.position = (self.position + len - 1) % len,
+            Message::SelectSaliencyMethod(method) => {
+                self.saliency_method = method;
+                self.refresh_saliency();
+                return;
+            }
+            Message::SelectMode(mode) => self.mode = mode,
+            Message::StrokeStarted(point) => {
+                self.drawing.start_stroke(point);
+                // The saliency map is only updated once the stroke is finished
+                self.refresh_prediction();
+                return;
+            }
+            Message::StrokeExtended(point) => {
+                self.drawing.extend_stroke(point);
+                self.refresh_prediction();
+                return;
+            }
+            Message::StrokeEnded => {}
+            Message::ClearDrawing => self.drawing.clear(),
+            Message::BrushThicknessChanged(thickness) => self.drawing.thickness = thickness,
+            Message::SelectDataset(kind) => self.select_dataset(kind),
+            Message::SelectFilter(filter) => {
+                self.filter = filter;
+                self.apply_filter();
+            }
+            Message::SortByConfidence(sort_by_confidence) => {
+                self.sort_by_confidence = sort_by_confidence;
+                self.apply_filter();
+            }
+            Message::JumpInputChanged(jump_input) => {
+                self.jump_input = jump_input;
+                // Typing doesn't change the example
+                return;
+            }
+            Message::Jump => self.jump(),
+            Message::ExportFilters => {
+                self.export_status = match self.export_filters() {
+                    Ok(()) => format!(
+                        "Exported {} filters to {FILTERS_DIR}/",
+                        self.weight_maps.len()
+                    ),
+                    Err(err) => format!("Failed to export filters: {err}"),
+                };
+                return;
+            }
+            Message::ShowPair { label, predicted } => {
+                self.mode = Mode::Dataset;
+                self.filter = Filter::Pair { label, predicted };
+                self.apply_filter();
+            }
+        }
+        self.refresh();
+    }
+
+    /// Shows the probability of every class as a bar, the predicted class in green (red if it is
+    /// wrong) and the true class, if known, in blue.
+    fn probability_chart(&self, label: Option<u8>) -> Element<'_, Message> {
+        let mut ranking = (0..self.probabilities.len()).collect::<Vec<_>>();
+        ranking.sort_by(|&a, &b| self.probabilities[b].total_cmp(&self.probabilities[a]));
+        let top_3 = ranking
+            .iter()
+            .take(3)
+            .map(|&class| format!("{class} ({:.1}%)", self.probabilities[class] * 100.0))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let bars = self.probabilities.iter().enumerate().fold(
+            column![].spacing(2),
+            |column, (class, &probability)| {
+                let style = if class == self.prediction as usize {
+                    if label.is_none_or(|label| label == self.prediction) {
+                        progress_bar::success
+                    } else {
+                        progress_bar::danger
+                    }
+                } else if label == Some(class as u8) {
+                    progress_bar::primary
+                } else {
+                    progress_bar::secondary
+                };
+
+                column.push(
+                    row![
+                        text(class),
+                        progress_bar(0.0..=1.0, probability as f32)
+                            .width(200)
+                            .height(16)
+                            .style(style),
+                        text(format!("{:>5.1}%", probability * 100.0)),
+                    ]
+                    .spacing(5),
+                )
+            },
+        );
+
+        column![bars, text(format!("Top 3: {top_3}"))]
+            .spacing(5)
+            .into()
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let image_data = self
+            .input
+            .iter()
+            .map(|&x| (x * 255.0) as u8)
+            .flat_map(|pixel| [pixel, pixel, pixel, 255])
+            .collect::<Vec<_>>();
+
+        let prediction = self.prediction;
+
+        let saliency_methods = SaliencyMethod::ALL
+            .into_iter()
+            .map(Some)
+            .chain([None])
+            .fold(column![text("Saliency")].spacing(5), |column, method| {
+                let button = button(method.map_or("Off", SaliencyMethod::name));
+                column.push(if method == self.saliency_method {
+                    button
+                } else {
+                    button.on_press(Message::SelectSaliencyMethod(method))
+                })
+            });
+
+        let modes = row![
+            button("Dataset").on_press_maybe(
+                (self.mode != Mode::Dataset).then_some(Message::SelectMode(Mode::Dataset))
+            ),
+            button("Confusion Matrix").on_press_maybe(
+                (self.mode != Mode::ConfusionMatrix)
+                    .then_some(Message::SelectMode(Mode::ConfusionMatrix))
+            ),
+            button("Filters").on_press_maybe(
+                (self.mode != Mode::Filters).then_some(Message::SelectMode(Mode::Filters))
+            ),
+            button("Draw").on_press_maybe(
+                (self.mode != Mode::Draw).then_some(Message::SelectMode(Mode::Draw))
+            ),
+        ]
+        .spacing(5);
+
+        let dataset_kinds = row![
+            button("Train").on_press_maybe(
+                (self.dataset.kind != DatasetKind::Train)
+                    .then_some(Message::SelectDataset(DatasetKind::Train))
+            ),
+            button("Test").on_press_maybe(
+                (self.dataset.kind != DatasetKind::Test)
+                    .then_some(Message::SelectDataset(DatasetKind::Test))
+            ),
+        ]
+        .spacing(5);
+        let toolbar = if matches!(self.mode, Mode::Draw | Mode::Filters) {
+            modes
+        } else {
+            row![modes, dataset_kinds].spacing(20)
+        };
+
+        let images = row![
+            iced::widget::image::viewer(iced::advanced::image::Handle::from_rgba(
+                WIDTH, HEIGHT, image_data,
+            ))
+            .width((WIDTH * 10) as u16)
+            .height((HEIGHT * 10) as u16),
+            iced::widget::image::viewer(iced::advanced::image::Handle::from_rgba(
+                WIDTH,
+                HEIGHT,
+                saliency_image_data(&self.saliency),
+            ))
+            .width((WIDTH * 10) as u16)
+            .height((HEIGHT * 10) as u16),
+        ]
+        .spacing(10);
+
+        let content: Element<'_, Message> = match self.mode {
+            Mode::Dataset => {
+                let filters = row![
+                    pick_list(Filter::all(), Some(self.filter), Message::SelectFilter),
+                    checkbox("Lowest confidence first", self.sort_by_confidence)
+                        .on_toggle(Message::SortByConfidence),
+                    text_input("Jump to index", &self.jump_input)
+                        .on_input(Message::JumpInputChanged)
+                        .on_submit(Message::Jump)
+                        .width(150),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center);
+
+                let example = match self.data_index() {
+                    Some(data_index) => {
+                        let label = self.dataset.data[data_index].label;
+
+                        row![
+                            button("Previous").on_press(Message::Previous),
+                            column![
+                                images,
+                                text(format!(
+                                    "Index: {data_index} ({} of {})",
+                                    self.position + 1,
+                                    self.indices.len()
+                                )),
+                                text(format!("Label: {}", label)),
+                                text(format!("Prediction: {}", prediction)).color(
+                                    if label == prediction {
+                                        iced::Color::from_rgb(1.0, 1.0, 1.0)
+                                    } else {
+                                        iced::Color::from_rgb(1.0, 0.0, 0.0)
+                                    },
+                                ),
+                            ],
+                            self.probability_chart(Some(label)),
+                            saliency_methods,
+                            button("Next").on_press(Message::Next),
+                        ]
+                        .spacing(10)
+                    }
+                    None => row![text("No examples match the filter")],
+                };
+
+                column![filters, example].spacing(10).into()
+            }
+            Mode::ConfusionMatrix => confusion_matrix::view(&self.dataset),
+            Mode::Filters => self.filters_view(),
+            Mode::Draw => {
+                let canvas = canvas(DrawingCanvas {
+                    drawing: &self.drawing,
+                    on_stroke_start: Message::StrokeStarted,
+                    on_stroke_extend: Message::StrokeExtended,
+                    on_stroke_end: Message::StrokeEnded,
+                })
+                .width((WIDTH * 10) as u16)
+                .height((HEIGHT * 10) as u16);
+
+                row![
+                    column![
+                        canvas,
+                        row![
+                            text("Brush"),
+                            slider(
+                                0.03..=0.15,
+                                self.drawing.thickness,
+                                Message::BrushThicknessChanged
+                            )
+                            .step(0.01)
+                            .width(150),
+                            button("Clear").on_press_maybe(
+                                (!self.drawing.is_empty()).then_some(Message::ClearDrawing)
+                            ),
+                        ]
+                        .spacing(10),
+                    ]
+                    .spacing(10),
+                    column![images, text(format!("Prediction: {}", prediction))],
+                    self.probability_chart(None),
+                    saliency_methods,
+                ]
+                .spacing(10)
+                .into()
+            }
+        };
+
+        column![toolbar, content].spacing(10).into()
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    iced::application("Display Model Predictions", Viewer::update, Viewer::view)
+        .default_font(Font::MONOSPACE)
+        .run()?;
+
+    Ok(())
+}
@@ -1,128 +1,44 @@
 use anyhow::{Context, Result};
 use neural_net_mnist::{
+    adversarial::{Attack, adversarial_gradient_descent},
+    metrics::{Metrics, MetricsKind, MetricsLogger, gradient_norm},
+    mnist::{NUM_CLASSES, NUM_PIXELS, accuracy_function, load_data, loss_function},
+    model_file::{read_model_or_parameters, write_model},
     multi_layer_perceptron::MultiLayerPerceptron,
-    training::{GradientDescentResult, TrainingData, stochastic_gradient_descent},
-    value::Value,
+    training::{GradientDescentResult, RandomSampleIterator, stochastic_gradient_descent},
 };
 use std::fs::File;
-use std::io::{self, BufRead, Read, Write};
+use std::io::{self, Read};
 
-fn load_training_data() -> Result<Vec<TrainingData>> {
-    let file_path = "mnist_train.csv";
-    let file = File::open(file_path).with_context(|| {
-        format!(
-            "Failed to open {file_path}, \
-            download it from https://www.kaggle.com/datasets/oddrationale/mnist-in-csv"
-        )
-    })?;
-    let reader = io::BufReader::new(file);
-
-    let mut data = Vec::new();
-
-    for line in reader.lines().skip(1) {
-        let line = line.context("Failed to read line")?;
-        let mut tokens = line.split(',');
-
-        let label = tokens
-            .next()
-            .context("Expected label column")?
-            .parse::<u8>()
-            .context("Failed to parse label column into u8")?;
-        assert!(label <= 9);
-
-        let expected_output = (0..10)
-            .map(|i| if i == label { 1.0 } else { 0.0 })
-            .collect::<Vec<_>>();
-
-        let input = tokens
-            .map(|token| {
-                Ok(token
-                    .parse::<u8>()
-                    .context("Failed to parse pixel column into u8")? as f64
-                    / 255.0)
-            })
-            .collect::<Result<Vec<_>>>()?;
-        assert_eq!(input.len(), 784);
-
-        data.push(TrainingData::new(input, expected_output));
-    }
-
-    assert_eq!(data.len(), 60_000);
-
-    Ok(data)
-}
-
-fn write_model_to_file(model: &MultiLayerPerceptron, file: &File) -> Result<()> {
-    let mut writer = io::BufWriter::new(file);
-
-    for param in model.parameters() {
-        writer
-            .write_all(&param.data().to_le_bytes())
-            .context("Failed to write model to file")?;
-    }
-    writer.flush().context("Failed to flush model to file")?;
-
-    Ok(())
-}
-
-fn load_model_from_file(model: &MultiLayerPerceptron, file: &File) -> Result<()> {
-    let mut reader = io::BufReader::new(file);
-    let mut bytes = [0u8; 8];
-
-    for mut param in model.parameters() {
-        reader.read_exact(&mut bytes)?;
-        param.set_data(f64::from_le_bytes(bytes));
-    }
-
-    if reader.read(&mut bytes)? != 0 {
-        anyhow::bail!("Model file has extra unread bytes");
-    }
-
-    Ok(())
-}
-
-fn loss_function(output: &[Value], expected_output: &[f64]) -> Value {
-    output
-        .iter()
-        .zip(expected_output.iter().copied())
-        .map(|(o, e)| (o - &Value::new(e)).powf(2.0))
-        .fold(Value::new(0.0), |acc, cur| &acc + &cur)
-}
-
-fn accuracy_function(output: &[Value], expected_output: &[f64]) -> bool {
-    let mut max_output_index = 0;
-
-    for (i, o) in output.iter().enumerate() {
-        if o.data() > output[max_output_index].data() {
-            max_output_index = i;
+fn main() -> Result<()> {
+    let data = load_data("mnist_train.csv").context(
+        "Failed to load mnist_train.csv, \
+        download it from https://www.kaggle.com/datasets/oddrationale/mnist-in-csv",
+    )?;
+    // Continues training the saved model if there is one, with the architecture from its header.
+    // Older files without a header hold the parameters of this model and are converted on save.
+    let model_file = "model.bin";
+    let new_model = || MultiLayerPerceptron::new(NUM_PIXELS, &[50], NUM_CLASSES);
+    let model: MultiLayerPerceptron = match File::open(model_file) {
+        Ok(file) => {
+            read_model_or_parameters(file, new_model).context("Failed to load model from file")?
         }
-    }
-
-    let max_expected_index = expected_output.iter().position(|e| *e == 1.0).unwrap();
-
-    max_output_index == max_expected_index
-}
-
-fn linearly_interpolate(start: f64, end: f64, iterations: usize) -> impl Fn(usize) -> f64 {
-    let delta = end - start;
-    let step = if iterations <= 1 {
-        0.0
-    } else {
-        delta / (iterations - 1) as f64
+        Err(_) => new_model(),
     };
-    move |iter| start + step * iter.min(iterations - 1) as f64
-}
-
-fn main() -> Result<()> {
-    let data = load_training_data()?;
-    let model = MultiLayerPerceptron::new(784, &[50], 10);
+    println!("{model}");
     let batch_size = 1;
     let learning_rate = |_| 0.01;
-
-    let model_file = "model.bin";
-    if let Ok(file) = File::open(model_file) {
-        load_model_from_file(&model, &file)?;
-    }
+    // Set to e.g. Some(Attack::Fgsm(neural_net_mnist::adversarial::Budget::LInf(0.1))) to train
+    // on adversarial examples
+    let adversarial_attack: Option<Attack> = None;
+
+    // If a file is passed as the first argument, every step is logged there to plot the training
+    // curves offline. An existing file is overwritten.
+    let mut metrics_logger = std::env::args()
+        .nth(1)
+        .map(MetricsLogger::create)
+        .transpose()
+        .context("Failed to create metrics file")?;
 
     let handle = std::thread::spawn(move || {
         let mut stdio = io::stdin().lock();
@@ -140,18 +56,45 @@ fn main() -> Result<()> {
         let mut total_accuracy = 0.0;
 
         while last_timestamp.elapsed().as_secs() < 30 * 60 {
+            let step_start = std::time::Instant::now();
             let GradientDescentResult {
                 avg_loss,
                 avg_accuracy,
-            } = stochastic_gradient_descent(
-                &model,
-                &data,
-                batch_size,
-                iteration,
-                loss_function,
-                accuracy_function,
-                &learning_rate,
-            );
+                ..
+            } = match &adversarial_attack {
+                Some(attack) => adversarial_gradient_descent(
+                    &model,
+                    RandomSampleIterator::new(&data, batch_size)?,
+                    iteration,
+                    attack,
+                    loss_function,
+                    accuracy_function,
+                    &learning_rate,
+                ),
+                None => stochastic_gradient_descent(
+                    &model,
+                    &data,
+                    batch_size,
+                    iteration,
+                    loss_function,
+                    accuracy_function,
+                    &learning_rate,
+                ),
+            };
+
+            if let Some(metrics_logger) = &mut metrics_logger {
+                metrics_logger.log(&Metrics {
+                    kind: MetricsKind::Step,
+                    epoch: iteration * batch_size / data.len(),
+                    step: iteration + 1,
+                    loss: avg_loss,
+                    accuracy: avg_accuracy,
+                    learning_rate: Some(learning_rate(iteration)),
+                    grad_norm: Some(gradient_norm(&model)),
+                    num_examples: batch_size,
+                    duration: step_start.elapsed(),
+                })?;
+            }
 
             iteration += 1;
             inner_iterations += 1;
@@ -174,10 +117,17 @@ fn main() -> Result<()> {
             break;
         }
 
+        if let Some(metrics_logger) = &mut metrics_logger {
+            metrics_logger.flush()?;
+        }
         last_timestamp = std::time::Instant::now();
     }
 
-    write_model_to_file(&model, &File::create(model_file)?)?;
+    if let Some(metrics_logger) = &mut metrics_logger {
+        metrics_logger.flush()?;
+    }
+
+    write_model(&model, File::create(model_file)?).context("Failed to write model to file")?;
 
     Ok(())
 }
@@ -0,0 +1,98 @@
+use iced::{
+    Color, Point, Rectangle, Renderer, Size, Theme, mouse,
+    widget::canvas::{self, Frame, Geometry, Path, Stroke, Text},
+};
+
+/// A line chart of `points`, scaled to fit the canvas.
+pub struct LineChart<'a> {
+    pub title: &'a str,
+    pub points: &'a [(f64, f64)],
+    pub color: Color,
+}
+
+impl<Message> canvas::Program<Message> for LineChart<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let text_color = theme.palette().text;
+        let margin = 20.0;
+        let plot = Rectangle::new(
+            Point::new(margin, margin),
+            Size::new(bounds.width - 2.0 * margin, bounds.height - 2.0 * margin),
+        );
+
+        frame.stroke(
+            &Path::rectangle(plot.position(), plot.size()),
+            Stroke::default().with_color(Color {
+                a: 0.3,
+                ..text_color
+            }),
+        );
+
+        let last = self.points.last().map(|&(_, y)| format!(": {y:.5}"));
+        frame.fill_text(Text {
+            content: format!("{}{}", self.title, last.unwrap_or_default()),
+            position: Point::new(margin, 2.0),
+            color: text_color,
+            ..Text::default()
+        });
+
+        let Some(&(first_x, _)) = self.points.first() else {
+            return vec![frame.into_geometry()];
+        };
+        let last_x = self.points.last().unwrap().0;
+        let (min_y, max_y) = self
+            .points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &(_, y)| {
+                (min.min(y), max.max(y))
+            });
+        let x_range = (last_x - first_x).max(f64::EPSILON);
+        let y_range = (max_y - min_y).max(f64::EPSILON);
+
+        for (y, label) in [(plot.y, max_y), (plot.y + plot.height - 12.0, min_y)] {
+            frame.fill_text(Text {
+                content: format!("{label:.4}"),
+                position: Point::new(plot.x + 2.0, y),
+                color: Color {
+                    a: 0.6,
+                    ..text_color
+                },
+                size: 12.0.into(),
+                ..Text::default()
+            });
+        }
+
+        // Skip points that would end up on the same pixel column
+        let step = (self.points.len() / plot.width.max(1.0) as usize).max(1);
+        let to_plot = |&(x, y): &(f64, f64)| {
+            Point::new(
+                plot.x + ((x - first_x) / x_range) as f32 * plot.width,
+                plot.y + plot.height - ((y - min_y) / y_range) as f32 * plot.height,
+            )
+        };
+        let line = Path::new(|builder| {
+            for (i, point) in self.points.iter().step_by(step).enumerate() {
+                if i == 0 {
+                    builder.move_to(to_plot(point));
+                } else {
+                    builder.line_to(to_plot(point));
+                }
+            }
+        });
+        frame.stroke(
+            &line,
+            Stroke::default().with_color(self.color).with_width(2.0),
+        );
+
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::{Context, Result};
+use chart::LineChart;
+use iced::{
+    Color, Element, Font, Subscription,
+    futures::{SinkExt, channel::mpsc, executor::block_on},
+    widget::{button, canvas, column, image, row, text},
+};
+use neural_net_mnist::{
+    metrics::gradient_norm,
+    mnist::{NUM_CLASSES, NUM_PIXELS, accuracy_function, argmax, load_data, loss_function},
+    model_file::{read_model_or_parameters, write_model},
+    multi_layer_perceptron::MultiLayerPerceptron,
+    training::{GradientDescentResult, stochastic_gradient_descent},
+};
+use rand::seq::IndexedRandom;
+use std::{
+    fs::File,
+    sync::mpsc::{Receiver, Sender, TryRecvError},
+    time::Instant,
+};
+
+mod chart;
+
+const MODEL_FILE: &str = "model.bin";
+const BATCH_SIZE: usize = 1;
+/// Number of iterations averaged into one point of the curves.
+const REPORT_INTERVAL: usize = 100;
+/// Number of reports between refreshing the sample predictions.
+const SAMPLE_INTERVAL: usize = 10;
+const NUM_SAMPLES: usize = 8;
+
+/// Commands sent from the GUI to the training thread.
+enum Command {
+    Pause,
+    Resume,
+    Save,
+}
+
+#[derive(Debug, Clone)]
+struct Progress {
+    iteration: usize,
+    avg_loss: f64,
+    avg_accuracy: f64,
+    learning_rate: f64,
+    grad_norm: f64,
+    examples_per_second: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Sample {
+    image: image::Handle,
+    label: usize,
+    prediction: usize,
+}
+
+/// Events sent from the training thread to the GUI.
+#[derive(Debug, Clone)]
+enum Event {
+    Ready(Sender<Command>),
+    Progress(Progress),
+    Samples(Vec<Sample>),
+    Paused(bool),
+    Saved(String),
+    Failed(String),
+}
+
+/// Runs training on a background thread, because `Value`s can't be shared between threads the
+/// model lives entirely on it and the GUI only receives events.
+fn training_worker() -> impl iced::futures::Stream<Item = Event> {
+    iced::stream::channel(100, |mut output| async move {
+        let (command_sender, command_receiver) = std::sync::mpsc::channel();
+        let _ = output.send(Event::Ready(command_sender)).await;
+
+        std::thread::spawn(move || {
+            if let Err(err) = train(&command_receiver, &mut output) {
+                let _ = block_on(output.send(Event::Failed(format!("{err:#}"))));
+            }
+        });
+    })
+}
+
+fn train(commands: &Receiver<Command>, output: &mut mpsc::Sender<Event>) -> Result<()> {
+    let data = load_data("mnist_train.csv").context(
+        "Failed to load mnist_train.csv, \
+        download it from https://www.kaggle.com/datasets/oddrationale/mnist-in-csv",
+    )?;
+    // Older model files without a header hold the parameters of this model
+    let new_model = || MultiLayerPerceptron::new(NUM_PIXELS, &[50], NUM_CLASSES);
+    let model: MultiLayerPerceptron = match File::open(MODEL_FILE) {
+        Ok(file) => {
+            read_model_or_parameters(file, new_model).context("Failed to load model from file")?
+        }
+        Err(_) => new_model(),
+    };
+    let learning_rate = |_| 0.01;
+
+    let mut send = |event| block_on(output.send(event)).context("GUI closed");
+
+    let mut iteration = 0;
+    let mut paused = false;
+    let mut num_reports = 0;
+
+    loop {
+        let mut total_loss = 0.0;
+        let mut total_accuracy = 0.0;
+        let mut total_grad_norm = 0.0;
+        let start = Instant::now();
+
+        for _ in 0..REPORT_INTERVAL {
+            // Wait for commands while paused, otherwise only check for them
+            loop {
+                let command = if paused {
+                    commands.recv().map_err(|_| TryRecvError::Disconnected)
+                } else {
+                    commands.try_recv()
+                };
+                match command {
+                    Ok(Command::Pause) => paused = true,
+                    Ok(Command::Resume) => paused = false,
+                    Ok(Command::Save) => {
+                        let result = File::create(MODEL_FILE)
+                            .map_err(Into::into)
+                            .and_then(|file| write_model(&model, file));
+                        send(Event::Saved(match result {
+                            Ok(()) => format!("Saved to {MODEL_FILE} at iteration {iteration}"),
+                            Err(err) => format!("Failed to save: {err}"),
+                        }))?;
+                        continue;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return Ok(()),
+                }
+                send(Event::Paused(paused))?;
+            }
+
+            let GradientDescentResult {
+                avg_loss,
+                avg_accuracy,
+                ..
+            } = stochastic_gradient_descent(
+                &model,
+                &data,
+                BATCH_SIZE,
+                iteration,
+                loss_function,
+                accuracy_function,
+                learning_rate,
+            );
+
+            total_loss += avg_loss;
+            total_accuracy += avg_accuracy;
+            total_grad_norm += gradient_norm(&model);
+            iteration += 1;
+        }
+
+        send(Event::Progress(Progress {
+            iteration,
+            avg_loss: total_loss / REPORT_INTERVAL as f64,
+            avg_accuracy: total_accuracy / REPORT_INTERVAL as f64,
+            learning_rate: learning_rate(iteration),
+            grad_norm: total_grad_norm / REPORT_INTERVAL as f64,
+            examples_per_second: (REPORT_INTERVAL * BATCH_SIZE) as f64
+                / start.elapsed().as_secs_f64(),
+        }))?;
+
+        if num_reports % SAMPLE_INTERVAL == 0 {
+            let samples = data
+                .choose_multiple(&mut rand::rng(), NUM_SAMPLES)
+                .map(|example| {
+                    let output = model.predict(&example.input);
+                    let pixels = example
+                        .input
+                        .iter()
+                        .flat_map(|&x| {
+                            let pixel = (x * 255.0) as u8;
+                            [pixel, pixel, pixel, 255]
+                        })
+                        .collect::<Vec<_>>();
+
+                    Sample {
+                        image: image::Handle::from_rgba(28, 28, pixels),
+                        label: argmax(&example.expected_output),
+                        prediction: argmax(&output),
+                    }
+                })
+                .collect();
+            send(Event::Samples(samples))?;
+        }
+        num_reports += 1;
+    }
+}
+
+#[derive(Default)]
+struct Dashboard {
+    commands: Option<Sender<Command>>,
+    paused: bool,
+    losses: Vec<(f64, f64)>,
+    accuracies: Vec<(f64, f64)>,
+    learning_rates: Vec<(f64, f64)>,
+    grad_norms: Vec<(f64, f64)>,
+    latest: Option<Progress>,
+    samples: Vec<Sample>,
+    status: String,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Worker(Event),
+    Pause,
+    Resume,
+    Save,
+}
+
+impl Dashboard {
+    fn update(&mut self, message: Message) {
+        let command = match message {
+            Message::Worker(event) => {
+                self.handle_event(event);
+                return;
+            }
+            Message::Pause => Command::Pause,
+            Message::Resume => Command::Resume,
+            Message::Save => Command::Save,
+        };
+        if let Some(commands) = &self.commands {
+            let _ = commands.send(command);
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Ready(commands) => {
+                self.commands = Some(commands);
+                self.status = "Loading training data...".to_string();
+            }
+            Event::Progress(progress) => {
+                let x = progress.iteration as f64;
+                self.losses.push((x, progress.avg_loss));
+                self.accuracies.push((x, progress.avg_accuracy));
+                self.learning_rates.push((x, progress.learning_rate));
+                self.grad_norms.push((x, progress.grad_norm));
+                if self.latest.is_none() {
+                    self.status = "Training".to_string();
+                }
+                self.latest = Some(progress);
+            }
+            Event::Samples(samples) => self.samples = samples,
+            Event::Paused(paused) => {
+                self.paused = paused;
+                self.status = if paused { "Paused" } else { "Training" }.to_string();
+            }
+            Event::Saved(status) | Event::Failed(status) => self.status = status,
+        }
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let chart = |title, points, color| {
+            canvas(LineChart {
+                title,
+                points,
+                color,
+            })
+            .width(400)
+            .height(200)
+        };
+
+        let controls = row![
+            button("Pause").on_press_maybe((!self.paused).then_some(Message::Pause)),
+            button("Resume").on_press_maybe(self.paused.then_some(Message::Resume)),
+            button("Save").on_press(Message::Save),
+            text(&self.status),
+        ]
+        .spacing(10);
+
+        let summary = match &self.latest {
+            Some(progress) => text(format!(
+                "Iteration {}, {:.1} examples/s",
+                progress.iteration, progress.examples_per_second
+            )),
+            None => text("Waiting for the first iterations..."),
+        };
+
+        let samples = self.samples.iter().fold(row![].spacing(10), |row, sample| {
+            let color = if sample.label == sample.prediction {
+                Color::from_rgb(1.0, 1.0, 1.0)
+            } else {
+                Color::from_rgb(1.0, 0.0, 0.0)
+            };
+            row.push(column![
+                image(sample.image.clone()).width(84).height(84),
+                text(format!("{} -> {}", sample.label, sample.prediction)).color(color),
+            ])
+        });
+
+        column![
+            controls,
+            summary,
+            row![
+                chart("Loss", &self.losses, Color::from_rgb(0.9, 0.3, 0.3)),
+                chart("Accuracy", &self.accuracies, Color::from_rgb(0.3, 0.8, 0.3)),
+            ]
+            .spacing(10),
+            row![
+                chart(
+                    "Learning rate",
+                    &self.learning_rates,
+                    Color::from_rgb(0.3, 0.5, 0.9)
+                ),
+                chart(
+                    "Gradient norm",
+                    &self.grad_norms,
+                    Color::from_rgb(0.9, 0.7, 0.2)
+                ),
+            ]
+            .spacing(10),
+            text("Sample predictions (label -> prediction)"),
+            samples,
+        ]
+        .spacing(10)
+        .padding(10)
+        .into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::run(training_worker).map(Message::Worker)
+    }
+}
+
+fn main() -> iced::Result {
+    iced::application("Training Dashboard", Dashboard::update, Dashboard::view)
+        .subscription(Dashboard::subscription)
+        .default_font(Font::MONOSPACE)
+        .run()
+}
@@ -0,0 +1,56 @@
+use std::{fmt, iter};
+
+use crate::{float::Float, module::Module, value::Value};
+
+/// Element-wise activation function. Can be used on its own as a module or as part of a `Neuron`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activation {
+    Identity,
+    Tanh,
+    Relu,
+    Sigmoid,
+}
+
+impl Activation {
+    pub fn apply<F: Float>(&self, value: &Value<F>) -> Value<F> {
+        match self {
+            Activation::Identity => value.clone(),
+            Activation::Tanh => value.tanh(),
+            Activation::Relu => value.relu(),
+            Activation::Sigmoid => value.sigmoid(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Activation::Identity => "Identity",
+            Activation::Tanh => "Tanh",
+            Activation::Relu => "ReLU",
+            Activation::Sigmoid => "Sigmoid",
+        }
+    }
+}
+
+impl<F: Float> Module<F> for Activation {
+    fn forward(&self, inputs: &[Value<F>]) -> Vec<Value<F>> {
+        inputs.iter().map(|input| self.apply(input)).collect()
+    }
+
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_> {
+        Box::new(iter::empty())
+    }
+
+    fn name(&self) -> &str {
+        Activation::name(self)
+    }
+
+    fn activation(&self) -> Option<Activation> {
+        Some(*self)
+    }
+}
+
+impl fmt::Display for Activation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
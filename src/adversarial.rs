@@ -0,0 +1,449 @@
+use crate::{
+    error::{Error, Result},
+    float::Float,
+    module::Module,
+    optimizer::Sgd,
+    training::{GradientDescentResult, TrainingData, gradient_descent_step},
+    value::{Value, no_grad},
+};
+
+/// The set of allowed perturbations: every input value may change by at most the given amount
+/// (`LInf`), or the Euclidean length of the whole perturbation is bounded (`L2`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Budget {
+    LInf(f64),
+    L2(f64),
+}
+
+impl Budget {
+    /// Moves `input` by `step_size` in the direction of steepest ascent of the loss under this
+    /// norm.
+    fn step<F: Float>(&self, input: &mut [F], gradient: &[F], step_size: f64) {
+        match self {
+            Budget::LInf(_) => {
+                for (x, g) in input.iter_mut().zip(gradient) {
+                    let sign = if *g > F::ZERO {
+                        F::ONE
+                    } else if *g < F::ZERO {
+                        -F::ONE
+                    } else {
+                        F::ZERO
+                    };
+                    *x = *x + sign * F::from_f64(step_size);
+                }
+            }
+            Budget::L2(_) => {
+                let norm = l2_norm(gradient);
+                if norm > 0.0 {
+                    let scale = F::from_f64(step_size / norm);
+                    for (x, &g) in input.iter_mut().zip(gradient) {
+                        *x = *x + g * scale;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Projects `input` back into the budget around `original` and clamps it to valid pixel
+    /// values in [0, 1].
+    fn project<F: Float>(&self, input: &mut [F], original: &[F]) {
+        match *self {
+            Budget::LInf(epsilon) => {
+                for (x, &o) in input.iter_mut().zip(original) {
+                    let delta = (*x - o).to_f64().clamp(-epsilon, epsilon);
+                    *x = o + F::from_f64(delta);
+                }
+            }
+            Budget::L2(epsilon) => {
+                let delta = input
+                    .iter()
+                    .zip(original)
+                    .map(|(&x, &o)| x - o)
+                    .collect::<Vec<_>>();
+                let norm = l2_norm(&delta);
+                if norm > epsilon {
+                    let scale = F::from_f64(epsilon / norm);
+                    for ((x, &o), d) in input.iter_mut().zip(original).zip(delta) {
+                        *x = o + d * scale;
+                    }
+                }
+            }
+        }
+
+        for x in input.iter_mut() {
+            *x = F::from_f64(x.to_f64().clamp(0.0, 1.0));
+        }
+    }
+
+    fn epsilon(&self) -> f64 {
+        match *self {
+            Budget::LInf(epsilon) | Budget::L2(epsilon) => epsilon,
+        }
+    }
+}
+
+fn l2_norm<F: Float>(values: &[F]) -> f64 {
+    values
+        .iter()
+        .map(|v| v.to_f64() * v.to_f64())
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// An attack that perturbs an input within a `Budget` to maximize the loss.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attack {
+    /// Fast gradient sign method: a single step that uses up the whole budget.
+    Fgsm(Budget),
+    /// Projected gradient descent: `steps` steps of `step_size`, each followed by a projection
+    /// back into the budget.
+    Pgd {
+        budget: Budget,
+        step_size: f64,
+        steps: usize,
+    },
+}
+
+impl Attack {
+    /// Returns an error if the budget or step size is negative or not finite, or a PGD attack has
+    /// no steps.
+    pub fn validate(&self) -> Result<()> {
+        let (budget, step_size, steps) = self.parameters();
+        let epsilon = budget.epsilon();
+        if !(epsilon.is_finite() && epsilon >= 0.0) {
+            return Err(Error::InvalidArgument(format!(
+                "Attack budget must be finite and non-negative, got {epsilon}"
+            )));
+        }
+        if !(step_size.is_finite() && step_size >= 0.0) {
+            return Err(Error::InvalidArgument(format!(
+                "Attack step size must be finite and non-negative, got {step_size}"
+            )));
+        }
+        if steps == 0 {
+            return Err(Error::InvalidArgument(
+                "PGD attack needs at least one step".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Budget, step size and number of steps of the attack.
+    fn parameters(&self) -> (Budget, f64, usize) {
+        match *self {
+            Attack::Fgsm(budget) => (budget, budget.epsilon(), 1),
+            Attack::Pgd {
+                budget,
+                step_size,
+                steps,
+            } => (budget, step_size, steps),
+        }
+    }
+
+    /// Returns the adversarial version of `input`. Pixels are kept within [0, 1]. Like
+    /// `backward`, this overwrites the gradients of the model parameters.
+    pub fn perturb<F: Float>(
+        &self,
+        model: &(impl Module<F> + ?Sized),
+        input: &[F],
+        expected_output: &[F],
+        loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    ) -> Vec<F> {
+        self.try_perturb(model, input, expected_output, loss_function)
+            .unwrap()
+    }
+
+    /// Like `perturb`, but returns an error if `input` does not match the model or the attack is
+    /// invalid, see `validate`.
+    pub fn try_perturb<F: Float>(
+        &self,
+        model: &(impl Module<F> + ?Sized),
+        input: &[F],
+        expected_output: &[F],
+        mut loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    ) -> Result<Vec<F>> {
+        self.validate()?;
+        let (budget, step_size, steps) = self.parameters();
+
+        let mut adversarial = input.to_vec();
+        for _ in 0..steps {
+            let inputs = adversarial
+                .iter()
+                .map(|&x| Value::new(x))
+                .collect::<Vec<_>>();
+            let output = model.try_forward(&inputs)?;
+            loss_function(&output, expected_output).try_backward()?;
+
+            let gradient = inputs.iter().map(Value::grad).collect::<Vec<_>>();
+            budget.step(&mut adversarial, &gradient, step_size);
+            budget.project(&mut adversarial, input);
+        }
+
+        Ok(adversarial)
+    }
+}
+
+pub struct RobustnessResult {
+    pub clean_accuracy: f64,
+    pub robust_accuracy: f64,
+}
+
+/// Computes the accuracy of `model` on `data` both as is and with every input perturbed by
+/// `attack`.
+///
+/// Panics on the errors reported by `try_robust_accuracy`.
+pub fn robust_accuracy<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    data: impl Iterator<Item = &'a TrainingData<F>>,
+    attack: &Attack,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+) -> RobustnessResult {
+    try_robust_accuracy(model, data, attack, loss_function, accuracy_function).unwrap()
+}
+
+/// Like `robust_accuracy`, but returns an error if `data` is empty, an input does not match the
+/// model or the attack is invalid.
+pub fn try_robust_accuracy<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    data: impl Iterator<Item = &'a TrainingData<F>>,
+    attack: &Attack,
+    mut loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    mut accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+) -> Result<RobustnessResult> {
+    attack.validate()?;
+
+    let mut num_accurate = 0;
+    let mut num_robust = 0;
+    let mut num_examples = 0;
+
+    for TrainingData {
+        input,
+        expected_output,
+    } in data
+    {
+        let adversarial = attack.try_perturb(model, input, expected_output, &mut loss_function)?;
+
+        no_grad(|| {
+            for (input, num) in [(input, &mut num_accurate), (&adversarial, &mut num_robust)] {
+                let output = model
+                    .try_forward(&input.iter().copied().map(Value::new).collect::<Vec<_>>())?;
+                if accuracy_function(&output, expected_output) {
+                    *num += 1;
+                }
+            }
+            Ok::<_, Error>(())
+        })?;
+
+        num_examples += 1;
+    }
+
+    if num_examples == 0 {
+        return Err(Error::EmptyDataset);
+    }
+
+    Ok(RobustnessResult {
+        clean_accuracy: num_accurate as f64 / num_examples as f64,
+        robust_accuracy: num_robust as f64 / num_examples as f64,
+    })
+}
+
+/// Performs a gradient descent step like `gradient_descent`, but on the adversarial versions of
+/// the examples generated by `attack` against the current parameters. Use a
+/// `RandomSampleIterator` as `training_data` for stochastic gradient descent.
+pub fn adversarial_gradient_descent<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: impl Iterator<Item = &'a TrainingData<F>>,
+    iteration: usize,
+    attack: &Attack,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    learning_rate: impl FnMut(usize) -> f64,
+) -> GradientDescentResult {
+    adversarial_gradient_descent_step(
+        model,
+        training_data,
+        iteration,
+        attack,
+        loss_function,
+        accuracy_function,
+        learning_rate,
+        true,
+    )
+    .unwrap()
+}
+
+/// Like `adversarial_gradient_descent`, but returns an error instead of panicking, see
+/// `try_gradient_descent`.
+pub fn try_adversarial_gradient_descent<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: impl Iterator<Item = &'a TrainingData<F>>,
+    iteration: usize,
+    attack: &Attack,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    learning_rate: impl FnMut(usize) -> f64,
+) -> Result<GradientDescentResult> {
+    adversarial_gradient_descent_step(
+        model,
+        training_data,
+        iteration,
+        attack,
+        loss_function,
+        accuracy_function,
+        learning_rate,
+        false,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn adversarial_gradient_descent_step<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: impl Iterator<Item = &'a TrainingData<F>>,
+    iteration: usize,
+    attack: &Attack,
+    mut loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    learning_rate: impl FnMut(usize) -> f64,
+    skip_non_finite_loss: bool,
+) -> Result<GradientDescentResult> {
+    let adversarial_data = training_data
+        .map(|data| adversarial_example(model, data, attack, &mut loss_function))
+        .collect::<Result<Vec<_>>>()?;
+    gradient_descent_step(
+        model,
+        adversarial_data.iter(),
+        iteration,
+        loss_function,
+        accuracy_function,
+        learning_rate,
+        &mut Sgd,
+        skip_non_finite_loss,
+    )
+}
+
+fn adversarial_example<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    data: &TrainingData<F>,
+    attack: &Attack,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+) -> Result<TrainingData<F>> {
+    let input = attack.try_perturb(model, &data.input, &data.expected_output, loss_function)?;
+    Ok(TrainingData::new(input, data.expected_output.clone()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{activation::Activation, layer::Layer};
+
+    fn loss_function(output: &[Value], expected_output: &[f64]) -> Value {
+        output
+            .iter()
+            .zip(expected_output)
+            .fold(Value::new(0.0), |acc, (o, &e)| {
+                &acc + &(o - &Value::new(e)).powf(2.0)
+            })
+    }
+
+    fn accuracy_function(output: &[Value], expected_output: &[f64]) -> bool {
+        (output[0].data() > 0.5) == (expected_output[0] > 0.5)
+    }
+
+    #[test]
+    fn test_budgets() {
+        let model: Layer = Layer::with_activation(4, 1, Activation::Identity);
+        let input = [0.5; 4];
+
+        let adversarial =
+            Attack::Fgsm(Budget::LInf(0.1)).perturb(&model, &input, &[1.0], loss_function);
+        for (a, x) in adversarial.iter().zip(input) {
+            assert!((a - x).abs() <= 0.1 + 1e-12);
+        }
+
+        let attack = Attack::Pgd {
+            budget: Budget::L2(0.2),
+            step_size: 0.1,
+            steps: 5,
+        };
+        let adversarial = attack.perturb(&model, &input, &[1.0], loss_function);
+        let distance = adversarial
+            .iter()
+            .zip(input)
+            .map(|(a, x)| (a - x) * (a - x))
+            .sum::<f64>()
+            .sqrt();
+        assert!(distance <= 0.2 + 1e-12);
+        assert!(distance > 0.0);
+
+        // The attack increases the loss
+        let loss = |input: &[f64]| {
+            let output = model.forward(&input.iter().copied().map(Value::new).collect::<Vec<_>>());
+            loss_function(&output, &[1.0]).data()
+        };
+        assert!(loss(&adversarial) > loss(&input));
+    }
+
+    #[test]
+    fn test_robust_accuracy() {
+        let model: Layer = Layer::with_activation(4, 1, Activation::Identity);
+        let data = [TrainingData::new(vec![0.5; 4], vec![1.0])];
+
+        // Without a budget the attack can't change anything
+        let result = robust_accuracy(
+            &model,
+            data.iter(),
+            &Attack::Fgsm(Budget::LInf(0.0)),
+            loss_function,
+            accuracy_function,
+        );
+        assert_eq!(result.clean_accuracy, result.robust_accuracy);
+
+        assert!(matches!(
+            try_robust_accuracy(
+                &model,
+                data[..0].iter(),
+                &Attack::Fgsm(Budget::LInf(0.1)),
+                loss_function,
+                accuracy_function,
+            ),
+            Err(Error::EmptyDataset)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_attacks() {
+        let model: Layer = Layer::with_activation(4, 1, Activation::Identity);
+        let data = [TrainingData::new(vec![0.5; 4], vec![1.0])];
+
+        let pgd = |epsilon, step_size, steps| Attack::Pgd {
+            budget: Budget::L2(epsilon),
+            step_size,
+            steps,
+        };
+        for attack in [
+            Attack::Fgsm(Budget::LInf(-0.1)),
+            Attack::Fgsm(Budget::LInf(f64::NAN)),
+            Attack::Fgsm(Budget::L2(f64::INFINITY)),
+            pgd(0.1, -0.01, 5),
+            pgd(0.1, f64::NAN, 5),
+            pgd(0.1, 0.01, 0),
+        ] {
+            assert!(matches!(
+                attack.try_perturb(&model, &data[0].input, &[1.0], loss_function),
+                Err(Error::InvalidArgument(_))
+            ));
+            assert!(matches!(
+                try_robust_accuracy(
+                    &model,
+                    data[..0].iter(),
+                    &attack,
+                    loss_function,
+                    accuracy_function
+                ),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+        assert!(pgd(0.1, 0.01, 5).validate().is_ok());
+    }
+}
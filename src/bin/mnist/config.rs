@@ -0,0 +1,429 @@
+use anyhow::{Context, Result, ensure};
+use neural_net_mnist::{
+    activation::Activation,
+    initializer::Initializer,
+    optimizer::{Adam, Momentum, Optimizer, Sgd},
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, path::PathBuf};
+
+/// Everything needed to reproduce a training run. Read from a TOML or JSON file, missing fields
+/// take their default values.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExperimentConfig {
+    pub batch_size: usize,
+    pub epochs: usize,
+    /// Seed for the initial weights and the order of the examples.
+    pub seed: u64,
+    pub data: DataConfig,
+    pub model: ModelConfig,
+    pub optimizer: OptimizerConfig,
+    pub schedule: ScheduleConfig,
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 1,
+            epochs: 1,
+            seed: 0,
+            data: DataConfig::default(),
+            model: ModelConfig::default(),
+            optimizer: OptimizerConfig::Sgd,
+            schedule: ScheduleConfig::Constant {
+                learning_rate: 0.01,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataConfig {
+    /// Training data in CSV format.
+    pub train: PathBuf,
+    /// Test data in CSV format, evaluated after every epoch if given.
+    pub test: Option<PathBuf>,
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        Self {
+            train: PathBuf::from("mnist_train.csv"),
+            test: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelConfig {
+    pub hidden_layers: Vec<LayerConfig>,
+    #[serde(with = "ActivationDef")]
+    pub output_activation: Activation,
+    #[serde(with = "InitializerDef")]
+    pub initializer: Initializer,
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            hidden_layers: vec![LayerConfig {
+                size: 50,
+                activation: Activation::Tanh,
+            }],
+            output_activation: Activation::Tanh,
+            initializer: Initializer::Uniform,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayerConfig {
+    pub size: usize,
+    #[serde(with = "ActivationDef", default = "default_activation")]
+    pub activation: Activation,
+}
+
+fn default_activation() -> Activation {
+    Activation::Tanh
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Activation", rename_all = "lowercase")]
+enum ActivationDef {
+    Identity,
+    Tanh,
+    Relu,
+    Sigmoid,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Initializer", rename_all = "lowercase")]
+enum InitializerDef {
+    Uniform,
+    Xavier,
+    He,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum OptimizerConfig {
+    Sgd,
+    Momentum {
+        #[serde(default = "default_momentum")]
+        momentum: f64,
+    },
+    Adam {
+        #[serde(default = "default_beta1")]
+        beta1: f64,
+        #[serde(default = "default_beta2")]
+        beta2: f64,
+    },
+}
+
+pub fn default_momentum() -> f64 {
+    0.9
+}
+
+pub fn default_beta1() -> f64 {
+    0.9
+}
+
+pub fn default_beta2() -> f64 {
+    0.999
+}
+
+impl OptimizerConfig {
+    pub fn build(&self) -> Box<dyn Optimizer> {
+        match *self {
+            OptimizerConfig::Sgd => Box::new(Sgd),
+            OptimizerConfig::Momentum { momentum } => Box::new(Momentum::new(momentum)),
+            OptimizerConfig::Adam { beta1, beta2 } => Box::new(Adam::with_betas(beta1, beta2)),
+        }
+    }
+}
+
+/// Learning rate as a function of the training step.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ScheduleConfig {
+    Constant {
+        learning_rate: f64,
+    },
+    /// Linearly decreases from `learning_rate` to `final_learning_rate` over all epochs.
+    Linear {
+        learning_rate: f64,
+        final_learning_rate: f64,
+    },
+    /// Multiplies the learning rate by `factor` every `epochs` epochs.
+    Step {
+        learning_rate: f64,
+        factor: f64,
+        epochs: usize,
+    },
+}
+
+impl ScheduleConfig {
+    pub fn initial_learning_rate(&mut self) -> &mut f64 {
+        match self {
+            ScheduleConfig::Constant { learning_rate }
+            | ScheduleConfig::Linear { learning_rate, .. }
+            | ScheduleConfig::Step { learning_rate, .. } => learning_rate,
+        }
+    }
+
+    /// Learning rate at `iteration` of a run with `steps_per_epoch` steps in each of `epochs`
+    /// epochs.
+    pub fn learning_rate(&self, iteration: usize, steps_per_epoch: usize, epochs: usize) -> f64 {
+        match *self {
+            ScheduleConfig::Constant { learning_rate } => learning_rate,
+            ScheduleConfig::Linear {
+                learning_rate,
+                final_learning_rate,
+            } => {
+                let total_steps = steps_per_epoch * epochs;
+                let progress = if total_steps <= 1 {
+                    0.0
+                } else {
+                    iteration.min(total_steps - 1) as f64 / (total_steps - 1) as f64
+                };
+                learning_rate + (final_learning_rate - learning_rate) * progress
+            }
+            ScheduleConfig::Step {
+                learning_rate,
+                factor,
+                epochs,
+            } => {
+                let epoch = iteration / steps_per_epoch.max(1);
+                learning_rate * factor.powi((epoch / epochs.max(1)) as i32)
+            }
+        }
+    }
+}
+
+impl ExperimentConfig {
+    /// Reads a config from a JSON file if `path` ends with `.json`, otherwise from a TOML file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let config = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&text)?
+        } else {
+            toml::from_str(&text)?
+        };
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        ensure!(self.batch_size > 0, "Batch size must be positive");
+        ensure!(self.epochs > 0, "Number of epochs must be positive");
+        ensure!(
+            self.model.hidden_layers.iter().all(|layer| layer.size > 0),
+            "Layer sizes must be positive"
+        );
+        match self.optimizer {
+            OptimizerConfig::Sgd => {}
+            OptimizerConfig::Momentum { momentum } => {
+                ensure!((0.0..1.0).contains(&momentum), "Momentum must be in [0, 1)");
+            }
+            OptimizerConfig::Adam { beta1, beta2 } => {
+                ensure!(
+                    (0.0..1.0).contains(&beta1) && (0.0..1.0).contains(&beta2),
+                    "Adam betas must be in [0, 1)"
+                );
+            }
+        }
+
+        let mut schedule = self.schedule;
+        let learning_rate = *schedule.initial_learning_rate();
+        ensure!(
+            learning_rate.is_finite() && learning_rate > 0.0,
+            "Learning rate must be positive and finite"
+        );
+        match self.schedule {
+            ScheduleConfig::Constant { .. } => {}
+            ScheduleConfig::Linear {
+                final_learning_rate,
+                ..
+            } => {
+                ensure!(
+                    final_learning_rate.is_finite() && final_learning_rate >= 0.0,
+                    "Final learning rate must be non-negative and finite"
+                );
+            }
+            ScheduleConfig::Step { factor, epochs, .. } => {
+                ensure!(
+                    factor.is_finite() && factor > 0.0,
+                    "Step schedule factor must be positive and finite"
+                );
+                ensure!(
+                    epochs > 0,
+                    "Step schedule needs a positive number of epochs"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Hidden and output layers as expected by `MultiLayerPerceptron::from_layers`.
+    pub fn layers(&self, num_outputs: usize) -> Vec<(usize, Activation)> {
+        self.model
+            .hidden_layers
+            .iter()
+            .map(|layer| (layer.size, layer.activation))
+            .chain([(num_outputs, self.model.output_activation)])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let config = ExperimentConfig {
+            model: ModelConfig {
+                hidden_layers: vec![
+                    LayerConfig {
+                        size: 64,
+                        activation: Activation::Relu,
+                    },
+                    LayerConfig {
+                        size: 32,
+                        activation: Activation::Sigmoid,
+                    },
+                ],
+                output_activation: Activation::Identity,
+                initializer: Initializer::He,
+            },
+            optimizer: OptimizerConfig::Adam {
+                beta1: 0.8,
+                beta2: 0.99,
+            },
+            schedule: ScheduleConfig::Step {
+                learning_rate: 0.1,
+                factor: 0.5,
+                epochs: 2,
+            },
+            ..Default::default()
+        };
+
+        let toml = config.to_toml().unwrap();
+        assert_eq!(toml::from_str::<ExperimentConfig>(&toml).unwrap(), config);
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ExperimentConfig>(&json).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config: ExperimentConfig = toml::from_str(
+            r#"
+            epochs = 3
+
+            [[model.hidden_layers]]
+            size = 100
+
+            [optimizer]
+            kind = "momentum"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.epochs, 3);
+        assert_eq!(config.batch_size, 1);
+        assert_eq!(
+            config.model.hidden_layers,
+            vec![LayerConfig {
+                size: 100,
+                activation: Activation::Tanh,
+            }]
+        );
+        assert_eq!(
+            config.optimizer,
+            OptimizerConfig::Momentum { momentum: 0.9 }
+        );
+
+        assert!(toml::from_str::<ExperimentConfig>("epoch = 3").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ExperimentConfig::default().validate().is_ok());
+
+        let invalid = [
+            ExperimentConfig {
+                epochs: 0,
+                ..Default::default()
+            },
+            ExperimentConfig {
+                schedule: ScheduleConfig::Constant {
+                    learning_rate: f64::NAN,
+                },
+                ..Default::default()
+            },
+            ExperimentConfig {
+                schedule: ScheduleConfig::Constant { learning_rate: 0.0 },
+                ..Default::default()
+            },
+            ExperimentConfig {
+                schedule: ScheduleConfig::Linear {
+                    learning_rate: 0.1,
+                    final_learning_rate: f64::INFINITY,
+                },
+                ..Default::default()
+            },
+            ExperimentConfig {
+                optimizer: OptimizerConfig::Adam {
+                    beta1: 0.9,
+                    beta2: 1.0,
+                },
+                ..Default::default()
+            },
+            ExperimentConfig {
+                optimizer: OptimizerConfig::Adam {
+                    beta1: -0.1,
+                    beta2: 0.999,
+                },
+                ..Default::default()
+            },
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{config:?}");
+        }
+    }
+
+    #[test]
+    fn test_schedule() {
+        let linear = ScheduleConfig::Linear {
+            learning_rate: 1.0,
+            final_learning_rate: 0.0,
+        };
+        assert_eq!(linear.learning_rate(0, 5, 1), 1.0);
+        assert_eq!(linear.learning_rate(2, 5, 1), 0.5);
+        assert_eq!(linear.learning_rate(4, 5, 1), 0.0);
+
+        let step = ScheduleConfig::Step {
+            learning_rate: 1.0,
+            factor: 0.5,
+            epochs: 2,
+        };
+        assert_eq!(step.learning_rate(9, 5, 10), 1.0);
+        assert_eq!(step.learning_rate(10, 5, 10), 0.5);
+        assert_eq!(step.learning_rate(20, 5, 10), 0.25);
+    }
+}
@@ -0,0 +1,303 @@
+use anyhow::{Context, Result, bail, ensure};
+use clap::ValueEnum;
+use std::{fs, path::Path};
+
+use neural_net_mnist::mnist::{IMAGE_HEIGHT, IMAGE_WIDTH, NUM_PIXELS};
+
+/// Upper bound on the number of pixels of an input image, so a corrupted header is reported
+/// instead of allocating a huge image.
+const MAX_PIXELS: usize = 1 << 24;
+
+/// Whether to invert the image, MNIST digits are white on a black background.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Invert {
+    /// Invert if the border of the image is mostly bright.
+    Auto,
+    Always,
+    Never,
+}
+
+/// Grayscale image with intensities in [0, 1].
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<f64>,
+}
+
+/// Loads a PNG or PGM image as model input: converted to grayscale, scaled to 28x28 pixels and
+/// optionally inverted. The digit should fill most of the image, like in MNIST.
+pub fn load_input(path: &Path, invert: Invert) -> Result<Vec<f64>> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let image = if bytes.starts_with(b"\x89PNG") {
+        decode_png(&bytes)
+    } else if bytes.starts_with(b"P2") || bytes.starts_with(b"P5") {
+        decode_pgm(&bytes)
+    } else {
+        bail!("Unsupported image format, expected PNG or PGM")
+    }
+    .with_context(|| format!("Failed to decode {}", path.display()))?;
+
+    let mut input = image.resize(IMAGE_WIDTH, IMAGE_HEIGHT);
+    debug_assert_eq!(input.len(), NUM_PIXELS);
+
+    let invert = match invert {
+        Invert::Auto => border_mean(&input) > 0.5,
+        Invert::Always => true,
+        Invert::Never => false,
+    };
+    if invert {
+        input.iter_mut().for_each(|p| *p = 1.0 - *p);
+    }
+
+    Ok(input)
+}
+
+fn decode_png(bytes: &[u8]) -> Result<Image> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+
+    let channels = info.color_type.samples();
+    let (width, height) = (info.width as usize, info.height as usize);
+    num_pixels(width, height)?;
+    let pixels = (0..height)
+        .flat_map(|y| {
+            let row = &buffer[y * info.line_size..][..width * channels];
+            row.chunks(channels).map(|pixel| {
+                let pixel = pixel.iter().map(|&p| p as f64 / 255.0).collect::<Vec<_>>();
+                // Transparent pixels are treated as white, like most image viewers show them
+                let (gray, alpha) = match pixel.as_slice() {
+                    [gray] => (*gray, 1.0),
+                    [gray, alpha] => (*gray, *alpha),
+                    [r, g, b] => (luminance(*r, *g, *b), 1.0),
+                    [r, g, b, alpha] => (luminance(*r, *g, *b), *alpha),
+                    _ => unreachable!("PNG pixels have 1 to 4 channels"),
+                };
+                gray * alpha + (1.0 - alpha)
+            })
+        })
+        .collect();
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Number of pixels of an image with the given size from an untrusted header.
+fn num_pixels(width: usize, height: usize) -> Result<usize> {
+    ensure!(width > 0 && height > 0, "Image is empty");
+    width
+        .checked_mul(height)
+        .filter(|&num_pixels| num_pixels <= MAX_PIXELS)
+        .with_context(|| format!("Image size {width}x{height} is too large"))
+}
+
+fn luminance(r: f64, g: f64, b: f64) -> f64 {
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+/// Decodes a binary (P5) or plain (P2) PGM image with 8-bit or 16-bit samples.
+fn decode_pgm(bytes: &[u8]) -> Result<Image> {
+    let mut position = 0;
+    let mut next_token = || {
+        loop {
+            while bytes.get(position).is_some_and(u8::is_ascii_whitespace) {
+                position += 1;
+            }
+            if bytes.get(position) != Some(&b'#') {
+                break;
+            }
+            while bytes.get(position).is_some_and(|&b| b != b'\n') {
+                position += 1;
+            }
+        }
+        let start = position;
+        while bytes
+            .get(position)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        std::str::from_utf8(&bytes[start..position]).context("Invalid PGM header")
+    };
+
+    let magic = next_token()?.to_string();
+    let mut number = || -> Result<usize> {
+        let token = next_token()?;
+        token
+            .parse()
+            .with_context(|| format!("Expected a number in PGM header but got {token:?}"))
+    };
+    let (width, height, max_value) = (number()?, number()?, number()?);
+    let num_pixels = num_pixels(width, height)?;
+    ensure!(
+        (1..=u16::MAX as usize).contains(&max_value),
+        "Invalid PGM maximum value {max_value}"
+    );
+
+    let pixels = if magic == "P5" {
+        // A single whitespace character separates the header from the binary samples
+        let data = bytes.get(position + 1..).unwrap_or_default();
+        let sample_size = if max_value < 256 { 1 } else { 2 };
+        ensure!(
+            data.len() >= num_pixels * sample_size,
+            "PGM image data is truncated"
+        );
+        data.chunks(sample_size)
+            .take(num_pixels)
+            .map(|sample| match sample {
+                [value] => *value as usize,
+                [high, low] => u16::from_be_bytes([*high, *low]) as usize,
+                _ => unreachable!(),
+            })
+            .map(|value| value as f64 / max_value as f64)
+            .collect()
+    } else {
+        (0..num_pixels)
+            .map(|_| Ok(number()? as f64 / max_value as f64))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+impl Image {
+    /// Scales the image by averaging the pixels covered by every output pixel.
+    fn resize(&self, width: usize, height: usize) -> Vec<f64> {
+        let range = |i: usize, size: usize, source_size: usize| {
+            let start = i * source_size / size;
+            let end = ((i + 1) * source_size).div_ceil(size).max(start + 1);
+            start..end.min(source_size)
+        };
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (ys, xs) = (range(y, height, self.height), range(x, width, self.width));
+                let count = ys.len() * xs.len();
+                let sum = ys
+                    .flat_map(|y| xs.clone().map(move |x| (x, y)))
+                    .map(|(x, y)| self.pixels[y * self.width + x])
+                    .sum::<f64>();
+                pixels.push(sum / count as f64);
+            }
+        }
+        pixels
+    }
+}
+
+fn border_mean(pixels: &[f64]) -> f64 {
+    let border = (0..NUM_PIXELS)
+        .filter(|i| {
+            let (x, y) = (i % IMAGE_WIDTH, i / IMAGE_WIDTH);
+            x == 0 || y == 0 || x == IMAGE_WIDTH - 1 || y == IMAGE_HEIGHT - 1
+        })
+        .map(|i| pixels[i])
+        .collect::<Vec<_>>();
+    border.iter().sum::<f64>() / border.len() as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode_png(width: u32, height: u32, color_type: png::ColorType, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(color_type);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_decode_pgm() {
+        let image = decode_pgm(b"P2\n# comment\n2 2\n255\n0 255\n51 102\n").unwrap();
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.pixels, vec![0.0, 1.0, 0.2, 0.4]);
+
+        let image = decode_pgm(b"P2 1 2 1000 0 500").unwrap();
+        assert_eq!(image.pixels, vec![0.0, 0.5]);
+
+        let image = decode_pgm(b"P5\n3 1\n255\n\x00\x33\xff").unwrap();
+        assert_eq!(image.pixels, vec![0.0, 0.2, 1.0]);
+
+        let image = decode_pgm(b"P5 2 1 65535 \x00\x00\xff\xff").unwrap();
+        assert_eq!(image.pixels, vec![0.0, 1.0]);
+
+        for bytes in [
+            &b"P5\n2 2\n255\n\x00\x00\x00"[..],
+            b"P5 2 1 65535 \x00\x00\xff",
+            b"P2 2 2 255 0 0 0",
+            b"P2 0 2 255",
+            b"P2 2 2 0 0 0 0 0",
+            b"P2 2 x 255",
+            b"P5\n8589934592 8589934592\n255\n\x00",
+            b"P2 8589934592 8589934592 255 0",
+            b"P5 65536 65536 255 \x00",
+        ] {
+            assert!(decode_pgm(bytes).is_err());
+        }
+    }
+
+    #[test]
+    fn test_decode_png() {
+        let image = decode_png(&encode_png(2, 1, png::ColorType::Grayscale, &[0, 255])).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![0.0, 1.0]);
+
+        // Red, and a fully transparent pixel which is treated as white
+        let bytes = encode_png(2, 1, png::ColorType::Rgba, &[255, 0, 0, 255, 0, 0, 0, 0]);
+        let image = decode_png(&bytes).unwrap();
+        assert!((image.pixels[0] - 0.299).abs() < 1e-12);
+        assert_eq!(image.pixels[1], 1.0);
+
+        assert!(decode_png(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_resize() {
+        let image = Image {
+            width: 4,
+            height: 2,
+            pixels: vec![0.0, 1.0, 0.5, 0.5, 1.0, 0.0, 0.5, 0.5],
+        };
+        assert_eq!(image.resize(2, 1), vec![0.5, 0.5]);
+        assert_eq!(image.resize(1, 1), vec![0.5]);
+
+        let image = Image {
+            width: 1,
+            height: 1,
+            pixels: vec![0.25],
+        };
+        assert_eq!(image.resize(2, 3), vec![0.25; 6]);
+    }
+
+    #[test]
+    fn test_border_mean() {
+        let mut pixels = vec![0.0; NUM_PIXELS];
+        assert_eq!(border_mean(&pixels), 0.0);
+
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let (x, y) = (i % IMAGE_WIDTH, i / IMAGE_WIDTH);
+            if x == 0 || y == 0 || x == IMAGE_WIDTH - 1 || y == IMAGE_HEIGHT - 1 {
+                *pixel = 1.0;
+            }
+        }
+        assert_eq!(border_mean(&pixels), 1.0);
+
+        // Only the border counts
+        pixels[IMAGE_WIDTH + 1] = 0.5;
+        assert_eq!(border_mean(&pixels), 1.0);
+    }
+}
@@ -0,0 +1,468 @@
+use anyhow::{Context, Result, ensure};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use neural_net_mnist::{
+    activation::Activation,
+    metrics::{Metrics, MetricsKind, MetricsLogger, gradient_norm},
+    mnist::{self, NUM_CLASSES, NUM_PIXELS, accuracy_function, argmax, loss_function, softmax},
+    model_file::{read_header, read_model, write_model_with_metadata},
+    module::Module,
+    multi_layer_perceptron::MultiLayerPerceptron,
+    training::{
+        EvaluationResult, GradientDescentResult, TrainingData, try_evaluate,
+        try_gradient_descent_with_optimizer,
+    },
+    value::{Value, no_grad},
+};
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use std::{
+    fs::File,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use config::{
+    ExperimentConfig, LayerConfig, OptimizerConfig, default_beta1, default_beta2, default_momentum,
+};
+use image::{Invert, load_input};
+
+mod config;
+mod image;
+
+/// Train, evaluate and run multi-layer perceptrons on MNIST.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Train a new model and save it.
+    Train(TrainArgs),
+    /// Compute the loss and accuracy of a model on a dataset.
+    Eval(EvalArgs),
+    /// Classify a PNG or PGM image.
+    Predict(PredictArgs),
+    /// Print the architecture and training config stored in a model file.
+    Inspect(InspectArgs),
+}
+
+/// Options of `train`. Those that are given override the values from the config file.
+#[derive(Args)]
+struct TrainArgs {
+    /// Experiment config in TOML or JSON format, see `ExperimentConfig`.
+    #[arg(long, short)]
+    config: Option<PathBuf>,
+    /// Training data in CSV format.
+    #[arg(long)]
+    data: Option<PathBuf>,
+    /// Test data in CSV format, evaluated after every epoch if given.
+    #[arg(long)]
+    test_data: Option<PathBuf>,
+    /// Comma-separated sizes of the hidden layers, which keep the activation of the first
+    /// configured hidden layer.
+    #[arg(long, value_delimiter = ',')]
+    hidden: Option<Vec<usize>>,
+    #[arg(long, value_enum)]
+    optimizer: Option<OptimizerKind>,
+    /// Momentum coefficient, only used by the momentum optimizer.
+    #[arg(long)]
+    momentum: Option<f64>,
+    /// Initial learning rate of the schedule.
+    #[arg(long)]
+    learning_rate: Option<f64>,
+    #[arg(long)]
+    batch_size: Option<usize>,
+    #[arg(long)]
+    epochs: Option<usize>,
+    /// Seed for the initial weights and the order of the examples.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Number of steps between progress reports.
+    #[arg(long, default_value = "1000")]
+    report_interval: NonZeroUsize,
+    /// File to log the metrics of every step, epoch and evaluation to, in JSON Lines format if it
+    /// ends with `.jsonl` and CSV otherwise.
+    #[arg(long)]
+    metrics: Option<PathBuf>,
+    #[arg(long, short, default_value = "model.bin")]
+    output: PathBuf,
+}
+
+impl TrainArgs {
+    /// Reads the config file, or starts from the defaults, and applies the overrides.
+    fn resolve_config(&self) -> Result<ExperimentConfig> {
+        let mut config = match &self.config {
+            Some(path) => ExperimentConfig::load(path)
+                .with_context(|| format!("Invalid config {}", path.display()))?,
+            None => ExperimentConfig::default(),
+        };
+
+        if let Some(data) = &self.data {
+            config.data.train = data.clone();
+        }
+        if let Some(test_data) = &self.test_data {
+            config.data.test = Some(test_data.clone());
+        }
+        if let Some(hidden) = &self.hidden {
+            let activation = config
+                .model
+                .hidden_layers
+                .first()
+                .map_or(Activation::Tanh, |layer| layer.activation);
+            config.model.hidden_layers = hidden
+                .iter()
+                .map(|&size| LayerConfig { size, activation })
+                .collect();
+        }
+        match (self.optimizer, config.optimizer) {
+            (Some(OptimizerKind::Sgd), _) => config.optimizer = OptimizerConfig::Sgd,
+            (Some(OptimizerKind::Momentum), OptimizerConfig::Momentum { .. }) => {}
+            (Some(OptimizerKind::Momentum), _) => {
+                config.optimizer = OptimizerConfig::Momentum {
+                    momentum: default_momentum(),
+                }
+            }
+            (Some(OptimizerKind::Adam), OptimizerConfig::Adam { .. }) => {}
+            (Some(OptimizerKind::Adam), _) => {
+                config.optimizer = OptimizerConfig::Adam {
+                    beta1: default_beta1(),
+                    beta2: default_beta2(),
+                }
+            }
+            (None, _) => {}
+        }
+        if let (Some(value), OptimizerConfig::Momentum { momentum }) =
+            (self.momentum, &mut config.optimizer)
+        {
+            *momentum = value;
+        }
+        if let Some(learning_rate) = self.learning_rate {
+            *config.schedule.initial_learning_rate() = learning_rate;
+        }
+        if let Some(batch_size) = self.batch_size {
+            config.batch_size = batch_size;
+        }
+        if let Some(epochs) = self.epochs {
+            config.epochs = epochs;
+        }
+        if let Some(seed) = self.seed {
+            config.seed = seed;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OptimizerKind {
+    Sgd,
+    Momentum,
+    Adam,
+}
+
+#[derive(Args)]
+struct EvalArgs {
+    /// Model file written by `train`.
+    model: PathBuf,
+    /// Test data in CSV format.
+    #[arg(long, default_value = "mnist_test.csv")]
+    data: PathBuf,
+}
+
+#[derive(Args)]
+struct PredictArgs {
+    /// Model file written by `train`.
+    model: PathBuf,
+    image: PathBuf,
+    #[arg(long, value_enum, default_value_t = Invert::Auto)]
+    invert: Invert,
+}
+
+#[derive(Args)]
+struct InspectArgs {
+    /// Model file written by `train`.
+    model: PathBuf,
+    /// Only print the training config, e.g. to reproduce the run with `train --config`.
+    #[arg(long)]
+    config: bool,
+}
+
+/// Like `mnist::load_data`, with a hint where to get the dataset.
+fn load_data(path: &Path) -> Result<Vec<TrainingData>> {
+    mnist::load_data(path).with_context(|| {
+        format!(
+            "Failed to load {}, \
+            download it from https://www.kaggle.com/datasets/oddrationale/mnist-in-csv",
+            path.display()
+        )
+    })
+}
+
+fn load_model(path: &PathBuf) -> Result<MultiLayerPerceptron> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let model =
+        read_model(file).with_context(|| format!("Failed to load model {}", path.display()))?;
+
+    let layer_sizes = model.layer_sizes();
+    ensure!(
+        layer_sizes[0] == NUM_PIXELS && layer_sizes[layer_sizes.len() - 1] == NUM_CLASSES,
+        "Model has {} inputs and {} outputs, expected {NUM_PIXELS} and {NUM_CLASSES}",
+        layer_sizes[0],
+        layer_sizes[layer_sizes.len() - 1]
+    );
+
+    Ok(model)
+}
+
+fn train(args: TrainArgs) -> Result<()> {
+    let config = args.resolve_config()?;
+    let config_toml = config.to_toml()?;
+    println!("{config_toml}");
+
+    let data = load_data(&config.data.train)?;
+    let test_data = config.data.test.as_deref().map(load_data).transpose()?;
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let model = MultiLayerPerceptron::from_layers(
+        NUM_PIXELS,
+        &config.layers(NUM_CLASSES),
+        config.model.initializer,
+        &mut rng,
+    );
+    println!("{model}");
+
+    let mut optimizer = config.optimizer.build();
+    let steps_per_epoch = data.len().div_ceil(config.batch_size);
+    let learning_rate = |iteration| {
+        config
+            .schedule
+            .learning_rate(iteration, steps_per_epoch, config.epochs)
+    };
+
+    let mut metrics_logger = args
+        .metrics
+        .as_ref()
+        .map(|path| {
+            MetricsLogger::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))
+        })
+        .transpose()?;
+    let mut log = |metrics: Metrics| -> Result<()> {
+        if let Some(logger) = &mut metrics_logger {
+            logger.log(&metrics).context("Failed to log metrics")?;
+        }
+        Ok(())
+    };
+
+    let mut indices = (0..data.len()).collect::<Vec<_>>();
+    let mut iteration = 0;
+
+    for epoch in 1..=config.epochs {
+        indices.shuffle(&mut rng);
+
+        let (mut total_loss, mut total_accuracy, mut total_grad_norm) = (0.0, 0.0, 0.0);
+        let mut num_steps = 0;
+        let start = Instant::now();
+
+        for batch in indices.chunks(config.batch_size) {
+            let step_start = Instant::now();
+            let GradientDescentResult {
+                avg_loss,
+                avg_accuracy,
+                ..
+            } = try_gradient_descent_with_optimizer(
+                &model,
+                batch.iter().map(|&i| &data[i]),
+                iteration,
+                loss_function,
+                accuracy_function,
+                &learning_rate,
+                optimizer.as_mut(),
+            )
+            .with_context(|| format!("Training step {iteration} failed"))?;
+            let grad_norm = gradient_norm(&model);
+
+            log(Metrics {
+                kind: MetricsKind::Step,
+                epoch,
+                step: iteration + 1,
+                loss: avg_loss,
+                accuracy: avg_accuracy,
+                learning_rate: Some(learning_rate(iteration)),
+                grad_norm: Some(grad_norm),
+                num_examples: batch.len(),
+                duration: step_start.elapsed(),
+            })?;
+
+            iteration += 1;
+            total_loss += avg_loss;
+            total_accuracy += avg_accuracy;
+            total_grad_norm += grad_norm;
+            num_steps += 1;
+
+            if num_steps % args.report_interval.get() == 0 {
+                println!(
+                    "Epoch {epoch}, step {num_steps}: avg loss {:.4}, avg accuracy {:.4}",
+                    total_loss / num_steps as f64,
+                    total_accuracy / num_steps as f64
+                );
+            }
+        }
+
+        let epoch_metrics = Metrics {
+            kind: MetricsKind::Epoch,
+            epoch,
+            step: iteration,
+            loss: total_loss / num_steps as f64,
+            accuracy: total_accuracy / num_steps as f64,
+            learning_rate: Some(learning_rate(iteration.saturating_sub(1))),
+            grad_norm: Some(total_grad_norm / num_steps as f64),
+            num_examples: data.len(),
+            duration: start.elapsed(),
+        };
+        println!(
+            "Epoch {epoch} done in {:.1?}: avg loss {:.4}, avg accuracy {:.4}",
+            epoch_metrics.duration, epoch_metrics.loss, epoch_metrics.accuracy
+        );
+        log(epoch_metrics)?;
+
+        if let Some(test_data) = &test_data {
+            let start = Instant::now();
+            let EvaluationResult {
+                avg_loss,
+                avg_accuracy,
+            } = try_evaluate(&model, test_data.iter(), loss_function, accuracy_function)?;
+            println!("Test: avg loss {avg_loss:.4}, avg accuracy {avg_accuracy:.4}");
+            log(Metrics {
+                kind: MetricsKind::Eval,
+                epoch,
+                step: iteration,
+                loss: avg_loss,
+                accuracy: avg_accuracy,
+                learning_rate: None,
+                grad_norm: None,
+                num_examples: test_data.len(),
+                duration: start.elapsed(),
+            })?;
+        }
+
+        let file = File::create(&args.output)
+            .with_context(|| format!("Failed to create {}", args.output.display()))?;
+        write_model_with_metadata(&model, &config_toml, file).context("Failed to save model")?;
+        println!("Saved model to {}", args.output.display());
+    }
+
+    if let Some(logger) = &mut metrics_logger {
+        logger.flush().context("Failed to log metrics")?;
+    }
+
+    Ok(())
+}
+
+fn eval(args: EvalArgs) -> Result<()> {
+    let model = load_model(&args.model)?;
+    let data = load_data(&args.data)?;
+
+    let EvaluationResult {
+        avg_loss,
+        avg_accuracy,
+    } = try_evaluate(&model, data.iter(), loss_function, accuracy_function)?;
+    println!("Examples: {}", data.len());
+    println!("Avg loss: {avg_loss:.4}");
+    println!("Accuracy: {avg_accuracy:.4}");
+
+    let mut correct = [0; NUM_CLASSES];
+    let mut total = [0; NUM_CLASSES];
+    no_grad(|| {
+        for example in &data {
+            let input = example.input.iter().copied().map(Value::new);
+            let output = model.forward(&input.collect::<Vec<_>>());
+            let output = output.iter().map(Value::data).collect::<Vec<_>>();
+
+            let label = argmax(&example.expected_output);
+            total[label] += 1;
+            if argmax(&output) == label {
+                correct[label] += 1;
+            }
+        }
+    });
+
+    println!("Per-class accuracy:");
+    for class in 0..NUM_CLASSES {
+        if total[class] > 0 {
+            println!(
+                "  {class}: {:.4} ({}/{})",
+                correct[class] as f64 / total[class] as f64,
+                correct[class],
+                total[class]
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn predict(args: PredictArgs) -> Result<()> {
+    let model = load_model(&args.model)?;
+    let input = load_input(&args.image, args.invert)?;
+
+    let output = no_grad(|| {
+        let input = input.into_iter().map(Value::new).collect::<Vec<_>>();
+        model
+            .forward(&input)
+            .iter()
+            .map(Value::data)
+            .collect::<Vec<_>>()
+    });
+    let probabilities = softmax(&output);
+
+    println!("Prediction: {}", argmax(&output));
+    for (class, probability) in probabilities.iter().enumerate() {
+        println!("  {class}: {probability:.4}");
+    }
+
+    Ok(())
+}
+
+fn inspect(args: InspectArgs) -> Result<()> {
+    let file = File::open(&args.model)
+        .with_context(|| format!("Failed to open {}", args.model.display()))?;
+    let header = read_header(file)
+        .with_context(|| format!("Failed to read header of {}", args.model.display()))?;
+
+    if args.config {
+        ensure!(
+            !header.metadata.is_empty(),
+            "{} has no training config",
+            args.model.display()
+        );
+        print!("{}", header.metadata);
+        return Ok(());
+    }
+
+    let float_type = match header.float_size {
+        4 => "f32".to_string(),
+        8 => "f64".to_string(),
+        size => format!("{size}-byte floats"),
+    };
+    println!("Parameter type: {float_type}");
+    println!("{}", header.summary()?);
+    if !header.metadata.is_empty() {
+        println!("\nTraining config:\n{}", header.metadata);
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Train(args) => train(args),
+        Command::Eval(args) => eval(args),
+        Command::Predict(args) => predict(args),
+        Command::Inspect(args) => inspect(args),
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{activation::Activation, error::Result, float::Float, module::Module, value::Value};
+
+/// Feeds the same input to several branches and concatenates their outputs (DenseNet-style).
+pub struct Concat<F: Float = f64> {
+    branches: Vec<Box<dyn Module<F>>>,
+}
+
+impl<F: Float> Concat<F> {
+    pub fn new(branches: Vec<Box<dyn Module<F>>>) -> Self {
+        Self { branches }
+    }
+
+    /// Concatenates the input with the output of `module`, i.e. a dense connection.
+    pub fn dense(module: impl Module<F> + 'static) -> Self {
+        Self::new(vec![Box::new(Activation::Identity), Box::new(module)])
+    }
+}
+
+impl<F: Float> Module<F> for Concat<F> {
+    fn forward(&self, inputs: &[Value<F>]) -> Vec<Value<F>> {
+        self.branches
+            .iter()
+            .flat_map(|branch| branch.forward(inputs))
+            .collect()
+    }
+
+    fn try_forward(&self, inputs: &[Value<F>]) -> Result<Vec<Value<F>>> {
+        let mut outputs = Vec::new();
+        for branch in self.branches.iter() {
+            outputs.extend(branch.try_forward(inputs)?);
+        }
+        Ok(outputs)
+    }
+
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_> {
+        Box::new(self.branches.iter().flat_map(|branch| branch.parameters()))
+    }
+
+    fn name(&self) -> &str {
+        "Concat"
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        self.branches
+            .iter()
+            .map(|branch| branch.output_size(input_size))
+            .sum()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        for branch in self.branches.iter_mut() {
+            branch.set_training(training);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use rand::prelude::*;
+use std::iter;
+
+use crate::{float::Float, module::Module, value::Value};
+
+/// Randomly zeroes inputs with probability `p` during training and scales the remaining ones by
+/// `1 / (1 - p)`. Acts as the identity in evaluation mode.
+pub struct Dropout {
+    p: f64,
+    training: bool,
+}
+
+impl Dropout {
+    pub fn new(p: f64) -> Self {
+        assert!((0.0..1.0).contains(&p));
+        Self { p, training: true }
+    }
+}
+
+impl<F: Float> Module<F> for Dropout {
+    fn forward(&self, inputs: &[Value<F>]) -> Vec<Value<F>> {
+        if !self.training || self.p == 0.0 {
+            return Vec::from(inputs);
+        }
+
+        let mut rng = rand::rng();
+        let scale = Value::new(F::from_f64(1.0 / (1.0 - self.p)));
+
+        inputs
+            .iter()
+            .map(|input| {
+                if rng.random::<f64>() < self.p {
+                    input * &Value::new(F::ZERO)
+                } else {
+                    input * &scale
+                }
+            })
+            .collect()
+    }
+
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_> {
+        Box::new(iter::empty())
+    }
+
+    fn name(&self) -> &str {
+        "Dropout"
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+}
@@ -0,0 +1,89 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The number of values passed to a model does not match what it expects.
+    DimensionMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// A class index is not smaller than the number of model outputs.
+    InvalidClass {
+        class: usize,
+        num_classes: usize,
+    },
+    /// No training examples were provided.
+    EmptyDataset,
+    /// An argument is outside of its valid range, e.g. zero steps.
+    InvalidArgument(String),
+    /// The loss of a training step is NaN or infinite.
+    NonFiniteLoss(f64),
+    /// Anomaly detection found a NaN or infinite value in the computation graph. `op` is the
+    /// operation that produced it and `depth` the distance of its node from the root.
+    Anomaly {
+        op: &'static str,
+        depth: usize,
+        value: f64,
+        in_backward: bool,
+    },
+    /// The computation graph contains a cycle.
+    CyclicGraph,
+    /// A model file does not match the architecture it is loaded into.
+    MalformedModelFile(String),
+    /// A dataset file could not be parsed, e.g. by `mnist::load_data`.
+    MalformedDataset(String),
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DimensionMismatch { expected, actual } => {
+                write!(f, "Expected {expected} values but got {actual}")
+            }
+            Error::InvalidClass { class, num_classes } => {
+                write!(f, "Class {class} is out of range for {num_classes} outputs")
+            }
+            Error::EmptyDataset => write!(f, "Dataset is empty"),
+            Error::InvalidArgument(reason) => write!(f, "Invalid argument: {reason}"),
+            Error::NonFiniteLoss(loss) => write!(f, "Loss is not finite: {loss}"),
+            Error::Anomaly {
+                op,
+                depth,
+                value,
+                in_backward,
+            } => {
+                let (kind, pass) = if *in_backward {
+                    ("gradient", "backward")
+                } else {
+                    ("value", "forward")
+                };
+                write!(
+                    f,
+                    "Non-finite {kind} {value} produced by {pass} pass of {op} at depth {depth}"
+                )
+            }
+            Error::CyclicGraph => write!(f, "Computation graph contains a cycle"),
+            Error::MalformedModelFile(reason) => write!(f, "Malformed model file: {reason}"),
+            Error::MalformedDataset(reason) => write!(f, "Malformed dataset: {reason}"),
+            Error::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -0,0 +1,37 @@
+use rand::distr::Uniform;
+
+/// How the initial weights of a `Layer` are sampled. Biases always start at zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Initializer {
+    /// Uniform in [-1, 1], independent of the layer size.
+    #[default]
+    Uniform,
+    /// Glorot/Xavier uniform, suited for `tanh` and sigmoid layers.
+    Xavier,
+    /// He/Kaiming uniform, suited for ReLU layers.
+    He,
+}
+
+impl Initializer {
+    /// Bound of the uniform distribution for a layer with `fan_in` inputs and `fan_out` neurons.
+    pub fn limit(&self, fan_in: usize, fan_out: usize) -> f64 {
+        match self {
+            Initializer::Uniform => 1.0,
+            Initializer::Xavier => (6.0 / (fan_in + fan_out).max(1) as f64).sqrt(),
+            Initializer::He => (6.0 / fan_in.max(1) as f64).sqrt(),
+        }
+    }
+
+    pub(crate) fn distribution(&self, fan_in: usize, fan_out: usize) -> Uniform<f64> {
+        let limit = self.limit(fan_in, fan_out);
+        Uniform::new_inclusive(-limit, limit).unwrap()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Initializer::Uniform => "Uniform",
+            Initializer::Xavier => "Xavier",
+            Initializer::He => "He",
+        }
+    }
+}
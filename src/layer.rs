@@ -1,26 +1,105 @@
-use crate::{neuron::Neuron, value::Value};
+use rand::Rng;
+use std::fmt;
 
-pub struct Layer {
-    neurons: Vec<Neuron>,
+use crate::{
+    activation::Activation, float::Float, initializer::Initializer, module::Module, neuron::Neuron,
+    value::Value,
+};
+
+/// Fully connected (dense) layer of neurons.
+#[derive(Debug)]
+pub struct Layer<F: Float = f64> {
+    neurons: Vec<Neuron<F>>,
 }
 
-impl Layer {
+impl<F: Float> Layer<F> {
     pub fn new(num_inputs: usize, num_neurons: usize) -> Self {
+        Self::with_activation(num_inputs, num_neurons, Activation::Tanh)
+    }
+
+    pub fn with_activation(num_inputs: usize, num_neurons: usize, activation: Activation) -> Self {
+        Self::with_rng(num_inputs, num_neurons, activation, &mut rand::rng())
+    }
+
+    pub fn with_rng(
+        num_inputs: usize,
+        num_neurons: usize,
+        activation: Activation,
+        rng: &mut impl Rng,
+    ) -> Self {
+        Self::with_initializer(
+            num_inputs,
+            num_neurons,
+            activation,
+            Initializer::Uniform,
+            rng,
+        )
+    }
+
+    /// Like `with_rng`, but samples the initial weights as described by `initializer`.
+    pub fn with_initializer(
+        num_inputs: usize,
+        num_neurons: usize,
+        activation: Activation,
+        initializer: Initializer,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let dist = initializer.distribution(num_inputs, num_neurons);
         Self {
             neurons: (0..num_neurons)
-                .map(|_| Neuron::new(num_inputs))
+                .map(|_| Neuron::with_distribution(num_inputs, activation, dist, rng))
                 .collect::<Vec<_>>(),
         }
     }
 
-    pub fn forward(&self, activations: &[Value]) -> Vec<Value> {
+    pub fn neurons(&self) -> &[Neuron<F>] {
+        &self.neurons
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.neurons.first().map_or(0, Neuron::num_inputs)
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.neurons.len()
+    }
+}
+
+impl<F: Float> Module<F> for Layer<F> {
+    fn forward(&self, activations: &[Value<F>]) -> Vec<Value<F>> {
         self.neurons
             .iter()
             .map(|neuron| neuron.forward(activations))
             .collect::<Vec<_>>()
     }
 
-    pub fn paramters(&self) -> impl Iterator<Item = Value> {
-        self.neurons.iter().flat_map(|neuron| neuron.parameters())
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_> {
+        Box::new(self.neurons.iter().flat_map(|neuron| neuron.parameters()))
+    }
+
+    fn name(&self) -> &str {
+        "Dense"
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        self.neurons.first().map(Neuron::num_inputs)
+    }
+
+    fn output_size(&self, _input_size: usize) -> usize {
+        self.num_outputs()
+    }
+
+    fn activation(&self) -> Option<Activation> {
+        self.neurons.first().map(Neuron::activation)
+    }
+}
+
+impl<F: Float> fmt::Display for Layer<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Dense({} -> {}", self.num_inputs(), self.num_outputs())?;
+        if let Some(activation) = self.activation() {
+            write!(f, ", {activation}")?;
+        }
+        write!(f, ")")
     }
 }
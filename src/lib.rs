@@ -1,5 +1,22 @@
-mod layer;
+pub mod activation;
+pub mod adversarial;
+pub mod concat;
+pub mod dropout;
+pub mod error;
+pub mod float;
+pub mod initializer;
+pub mod layer;
+pub mod metrics;
+pub mod mnist;
+pub mod model_file;
+pub mod module;
 pub mod multi_layer_perceptron;
-mod neuron;
+pub mod neuron;
+pub mod norm;
+pub mod optimizer;
+pub mod residual;
+pub mod saliency;
+pub mod sequential;
 pub mod training;
 pub mod value;
+pub mod weight_map;
@@ -0,0 +1,241 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{error::Result, float::Float, module::Module};
+
+/// What a `Metrics` record was computed on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricsKind {
+    /// A single gradient descent step.
+    Step,
+    /// The average over all steps of an epoch.
+    Epoch,
+    /// An evaluation on held-out data, without updating the parameters.
+    Eval,
+}
+
+impl MetricsKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MetricsKind::Step => "step",
+            MetricsKind::Epoch => "epoch",
+            MetricsKind::Eval => "eval",
+        }
+    }
+}
+
+/// One record written by `MetricsLogger`, e.g. built from a `GradientDescentResult`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    pub kind: MetricsKind,
+    pub epoch: usize,
+    /// Number of gradient descent steps so far.
+    pub step: usize,
+    pub loss: f64,
+    pub accuracy: f64,
+    /// `None` for evaluations.
+    pub learning_rate: Option<f64>,
+    /// See `gradient_norm`. `None` for evaluations.
+    pub grad_norm: Option<f64>,
+    /// Number of examples the record was computed on.
+    pub num_examples: usize,
+    /// Time it took to process the examples.
+    pub duration: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricsFormat {
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl MetricsFormat {
+    /// `JsonLines` for paths ending with `.jsonl` or `.json`, `Csv` otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "json") => MetricsFormat::JsonLines,
+            _ => MetricsFormat::Csv,
+        }
+    }
+}
+
+const COLUMNS: [&str; 9] = [
+    "kind",
+    "epoch",
+    "step",
+    "loss",
+    "accuracy",
+    "learning_rate",
+    "grad_norm",
+    "wall_time",
+    "examples_per_second",
+];
+
+/// Writes training metrics to a CSV or JSON Lines file so runs can be plotted and compared.
+/// Besides the fields of `Metrics`, every record contains the seconds since the logger was created
+/// and the throughput in examples per second. Missing and non-finite values are left empty.
+pub struct MetricsLogger<W: Write> {
+    writer: W,
+    format: MetricsFormat,
+    start: Instant,
+    wrote_header: bool,
+}
+
+impl MetricsLogger<io::BufWriter<File>> {
+    /// Creates the file at `path`, see `MetricsFormat::from_path` for the format.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)?;
+        Ok(Self::new(
+            io::BufWriter::new(file),
+            MetricsFormat::from_path(path),
+        ))
+    }
+}
+
+impl<W: Write> MetricsLogger<W> {
+    pub fn new(writer: W, format: MetricsFormat) -> Self {
+        Self {
+            writer,
+            format,
+            start: Instant::now(),
+            wrote_header: false,
+        }
+    }
+
+    pub fn log(&mut self, metrics: &Metrics) -> Result<()> {
+        let examples_per_second = metrics.num_examples as f64 / metrics.duration.as_secs_f64();
+        let values = [
+            metrics.epoch as f64,
+            metrics.step as f64,
+            metrics.loss,
+            metrics.accuracy,
+        ]
+        .map(Some)
+        .into_iter()
+        .chain([metrics.learning_rate, metrics.grad_norm])
+        .chain([self.start.elapsed().as_secs_f64(), examples_per_second].map(Some))
+        // Written as empty CSV fields and JSON nulls, e.g. the throughput of a zero duration
+        .map(|value| value.filter(|value| value.is_finite()));
+
+        match self.format {
+            MetricsFormat::Csv => {
+                if !self.wrote_header {
+                    writeln!(self.writer, "{}", COLUMNS.join(","))?;
+                    self.wrote_header = true;
+                }
+                write!(self.writer, "{}", metrics.kind.name())?;
+                for value in values {
+                    match value {
+                        Some(value) => write!(self.writer, ",{value}")?,
+                        None => write!(self.writer, ",")?,
+                    }
+                }
+                writeln!(self.writer)?;
+            }
+            MetricsFormat::JsonLines => {
+                write!(self.writer, "{{\"kind\":\"{}\"", metrics.kind.name())?;
+                for (column, value) in COLUMNS[1..].iter().zip(values) {
+                    match value {
+                        Some(value) => write!(self.writer, ",\"{column}\":{value}")?,
+                        None => write!(self.writer, ",\"{column}\":null")?,
+                    }
+                }
+                writeln!(self.writer, "}}")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flushes buffered records, e.g. to look at them while training is still running.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Euclidean norm of the gradients of all parameters of `model`, e.g. after a gradient descent
+/// step.
+pub fn gradient_norm<F: Float>(model: &(impl Module<F> + ?Sized)) -> f64 {
+    model
+        .parameters()
+        .map(|p| p.grad().to_f64().powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn metrics(kind: MetricsKind, learning_rate: Option<f64>) -> Metrics {
+        Metrics {
+            kind,
+            epoch: 1,
+            step: 10,
+            loss: 0.5,
+            accuracy: 0.75,
+            learning_rate,
+            grad_norm: learning_rate.map(|_| f64::NAN),
+            num_examples: 20,
+            duration: Duration::from_secs(2),
+        }
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut logger = MetricsLogger::new(Vec::new(), MetricsFormat::Csv);
+        logger.log(&metrics(MetricsKind::Step, Some(0.01))).unwrap();
+        logger.log(&metrics(MetricsKind::Eval, None)).unwrap();
+
+        let output = String::from_utf8(logger.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], COLUMNS.join(","));
+        assert!(lines[1].starts_with("step,1,10,0.5,0.75,0.01,,"));
+        assert!(lines[1].ends_with(",10"));
+        assert!(lines[2].starts_with("eval,1,10,0.5,0.75,,,"));
+
+        let mut logger = MetricsLogger::new(Vec::new(), MetricsFormat::Csv);
+        let mut instant = metrics(MetricsKind::Step, None);
+        instant.duration = Duration::ZERO;
+        logger.log(&instant).unwrap();
+        let output = String::from_utf8(logger.into_inner()).unwrap();
+        assert!(output.lines().nth(1).unwrap().ends_with(','));
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let mut logger = MetricsLogger::new(Vec::new(), MetricsFormat::JsonLines);
+        logger.log(&metrics(MetricsKind::Step, Some(0.01))).unwrap();
+        logger.log(&metrics(MetricsKind::Epoch, None)).unwrap();
+
+        let output = String::from_utf8(logger.into_inner()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(
+            "{\"kind\":\"step\",\"epoch\":1,\"step\":10,\"loss\":0.5,\"accuracy\":0.75,\
+            \"learning_rate\":0.01,\"grad_norm\":null,\"wall_time\":"
+        ));
+        assert!(lines[0].ends_with(",\"examples_per_second\":10}"));
+        assert!(lines[1].contains("\"learning_rate\":null"));
+
+        assert_eq!(
+            MetricsFormat::from_path(Path::new("run.jsonl")),
+            MetricsFormat::JsonLines
+        );
+        assert_eq!(
+            MetricsFormat::from_path(Path::new("run.csv")),
+            MetricsFormat::Csv
+        );
+    }
+}
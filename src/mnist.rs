@@ -0,0 +1,164 @@
+use std::{
+    fs::File,
+    io::{self, BufRead},
+    path::Path,
+};
+
+use crate::{
+    error::{Error, Result},
+    training::TrainingData,
+    value::Value,
+};
+
+pub const IMAGE_WIDTH: usize = 28;
+pub const IMAGE_HEIGHT: usize = 28;
+pub const NUM_PIXELS: usize = IMAGE_WIDTH * IMAGE_HEIGHT;
+pub const NUM_CLASSES: usize = 10;
+
+/// Loads a dataset in the CSV format of https://www.kaggle.com/datasets/oddrationale/mnist-in-csv,
+/// see `read_data`.
+pub fn load_data(path: impl AsRef<Path>) -> Result<Vec<TrainingData>> {
+    read_data(io::BufReader::new(File::open(path)?))
+}
+
+/// Reads a header line followed by one line per example with the label and 784 pixel values.
+/// The pixels are scaled to [0, 1] and the label is one-hot encoded. Returns an error if there are
+/// no examples.
+pub fn read_data(reader: impl BufRead) -> Result<Vec<TrainingData>> {
+    let mut data = Vec::new();
+
+    for (line_number, line) in reader.lines().enumerate().skip(1) {
+        let line = line?;
+        let malformed =
+            |reason: String| Error::MalformedDataset(format!("Line {}: {reason}", line_number + 1));
+        let mut tokens = line.split(',');
+
+        let label = tokens
+            .next()
+            .and_then(|token| token.parse::<usize>().ok())
+            .ok_or_else(|| malformed("Failed to parse label column into usize".to_string()))?;
+        if label >= NUM_CLASSES {
+            return Err(malformed(format!("Label {label} out of range")));
+        }
+
+        let expected_output = (0..NUM_CLASSES)
+            .map(|i| if i == label { 1.0 } else { 0.0 })
+            .collect::<Vec<_>>();
+
+        let input = tokens
+            .map(|token| {
+                let pixel = token.parse::<u8>().map_err(|_| {
+                    malformed(format!("Failed to parse pixel column {token:?} into u8"))
+                })?;
+                Ok(pixel as f64 / 255.0)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if input.len() != NUM_PIXELS {
+            return Err(malformed(format!(
+                "Expected {NUM_PIXELS} pixels but got {}",
+                input.len()
+            )));
+        }
+
+        data.push(TrainingData::new(input, expected_output));
+    }
+
+    if data.is_empty() {
+        return Err(Error::EmptyDataset);
+    }
+
+    Ok(data)
+}
+
+/// Sum of squared errors between `output` and the one-hot `expected_output`.
+pub fn loss_function(output: &[Value], expected_output: &[f64]) -> Value {
+    output
+        .iter()
+        .zip(expected_output.iter().copied())
+        .map(|(o, e)| (o - &Value::new(e)).powf(2.0))
+        .fold(Value::new(0.0), |acc, cur| &acc + &cur)
+}
+
+/// Whether the largest output is the expected class.
+pub fn accuracy_function(output: &[Value], expected_output: &[f64]) -> bool {
+    let output = output.iter().map(Value::data).collect::<Vec<_>>();
+    argmax(&output) == argmax(expected_output)
+}
+
+/// Index of the largest value, the first one on ties.
+pub fn argmax(values: &[f64]) -> usize {
+    let mut max_index = 0;
+
+    for (i, v) in values.iter().enumerate() {
+        if *v > values[max_index] {
+            max_index = i;
+        }
+    }
+
+    max_index
+}
+
+/// Turns model outputs into probabilities.
+pub fn softmax(values: &[f64]) -> Vec<f64> {
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let exps = values.iter().map(|v| (v - max).exp()).collect::<Vec<_>>();
+    let sum = exps.iter().sum::<f64>();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn line(label: &str, num_pixels: usize) -> String {
+        let pixels = (0..num_pixels).map(|i| (i % 256).to_string());
+        [label.to_string()]
+            .into_iter()
+            .chain(pixels)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    #[test]
+    fn test_read_data() {
+        let csv = format!("label,pixels\n{}\n{}\n", line("3", 784), line("0", 784));
+        let data = read_data(csv.as_bytes()).unwrap();
+        assert_eq!(data.len(), 2);
+        assert_eq!(argmax(&data[0].expected_output), 3);
+        assert_eq!(data[1].expected_output.iter().sum::<f64>(), 1.0);
+        assert_eq!(data[0].input.len(), NUM_PIXELS);
+        assert_eq!(data[0].input[255], 1.0);
+
+        for csv in [
+            format!("header\n{}\n", line("10", 784)),
+            format!("header\n{}\n", line("x", 784)),
+            format!("header\n{}\n", line("1", 783)),
+            format!("header\n{}\n", line("1", 784).replace(",255,", ",256,")),
+        ] {
+            assert!(matches!(
+                read_data(csv.as_bytes()),
+                Err(Error::MalformedDataset(_))
+            ));
+        }
+
+        assert!(matches!(
+            read_data("label,pixels\n".as_bytes()),
+            Err(Error::EmptyDataset)
+        ));
+        assert!(matches!(read_data(&b""[..]), Err(Error::EmptyDataset)));
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(argmax(&[0.1, 0.7, 0.7, -1.0]), 1);
+
+        let probabilities = softmax(&[1.0, 2.0, 3.0]);
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(argmax(&probabilities), 2);
+
+        let output = [Value::new(0.2), Value::new(0.9)];
+        assert!(accuracy_function(&output, &[0.0, 1.0]));
+        assert!(!accuracy_function(&output, &[1.0, 0.0]));
+        assert!((loss_function(&output, &[0.0, 1.0]).data() - 0.05).abs() < 1e-12);
+    }
+}
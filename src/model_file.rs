@@ -0,0 +1,582 @@
+use rand::Rng;
+use std::{
+    fmt::Write as _,
+    io::{self, Read, Write},
+};
+
+use crate::{
+    activation::Activation,
+    error::{Error, Result},
+    float::Float,
+    initializer::Initializer,
+    module::Module,
+    multi_layer_perceptron::MultiLayerPerceptron,
+};
+
+const MAGIC: &[u8; 8] = b"NNMNIST\0";
+/// Version 2 added the layer activations and the metadata.
+const VERSION: u32 = 2;
+/// Upper bound on the number of parameters in a model file, so a corrupted header is reported
+/// instead of allocating a huge model. Every parameter is a node of the computation graph, so
+/// this already takes hundreds of megabytes.
+const MAX_PARAMETERS: usize = 1 << 22;
+
+/// Architecture of a `MultiLayerPerceptron`, stored at the start of files written by
+/// `write_model` so they can be loaded and inspected without knowing it in advance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelHeader {
+    /// Size of a parameter in bytes, 4 for `f32` and 8 for `f64`.
+    pub float_size: usize,
+    /// See `MultiLayerPerceptron::layer_sizes`.
+    pub layer_sizes: Vec<usize>,
+    /// Activation of every layer after the input.
+    pub activations: Vec<Activation>,
+    /// Free-form text stored by `write_model_with_metadata`, e.g. how the model was trained.
+    pub metadata: String,
+}
+
+impl ModelHeader {
+    /// Returns an error if the architecture is invalid or has more parameters than a model file
+    /// may contain.
+    pub fn num_parameters(&self) -> Result<usize> {
+        if self.layer_sizes.len() < 2 {
+            return Err(Error::MalformedModelFile(
+                "Model needs at least an input and an output layer".to_string(),
+            ));
+        }
+        if self.activations.len() != self.layer_sizes.len() - 1 {
+            return Err(Error::MalformedModelFile(format!(
+                "Expected {} activations but found {}",
+                self.layer_sizes.len() - 1,
+                self.activations.len()
+            )));
+        }
+        if self.layer_sizes.contains(&0) {
+            return Err(Error::MalformedModelFile(
+                "Layer sizes must be positive".to_string(),
+            ));
+        }
+
+        self.layer_sizes
+            .windows(2)
+            .try_fold(0usize, |total, w| {
+                w[0].checked_add(1)?.checked_mul(w[1])?.checked_add(total)
+            })
+            .filter(|&total| total <= MAX_PARAMETERS)
+            .ok_or_else(|| {
+                Error::MalformedModelFile(format!(
+                    "Model has more than {MAX_PARAMETERS} parameters"
+                ))
+            })
+    }
+
+    /// Same table as `MultiLayerPerceptron::summary`, computed from the header alone so it doesn't
+    /// need to allocate the model.
+    pub fn summary(&self) -> Result<String> {
+        const RULE_WIDTH: usize = 64;
+
+        let num_parameters = self.num_parameters()?;
+        let mut summary = String::new();
+
+        writeln!(
+            summary,
+            "{:<20}{:>14}{:>14}{:>16}",
+            "Layer (type)", "Output Shape", "Param #", "Activation"
+        )
+        .unwrap();
+        writeln!(summary, "{}", "=".repeat(RULE_WIDTH)).unwrap();
+
+        for (i, (sizes, activation)) in self
+            .layer_sizes
+            .windows(2)
+            .zip(&self.activations)
+            .enumerate()
+        {
+            writeln!(
+                summary,
+                "{:<20}{:>14}{:>14}{:>16}",
+                format!("Dense ({i})"),
+                format!("({})", sizes[1]),
+                (sizes[0] + 1) * sizes[1],
+                activation.to_string()
+            )
+            .unwrap();
+        }
+
+        writeln!(summary, "{}", "=".repeat(RULE_WIDTH)).unwrap();
+        write!(summary, "Total params: {num_parameters}").unwrap();
+
+        Ok(summary)
+    }
+
+    /// Creates a randomly initialized model with the architecture described by the header.
+    /// Returns the errors of `num_parameters`.
+    pub fn build_model<F: Float>(&self, rng: &mut impl Rng) -> Result<MultiLayerPerceptron<F>> {
+        self.num_parameters()?;
+        let layers = self.layer_sizes[1..]
+            .iter()
+            .copied()
+            .zip(self.activations.iter().copied())
+            .collect::<Vec<_>>();
+        Ok(MultiLayerPerceptron::from_layers(
+            self.layer_sizes[0],
+            &layers,
+            Initializer::Uniform,
+            rng,
+        ))
+    }
+}
+
+/// Writes all parameters of `model` in the order of `Module::parameters` as little endian floats
+/// of type `F`.
+pub fn write_parameters<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    writer: impl Write,
+) -> Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+
+    for param in model.parameters() {
+        writer.write_all(param.data().to_le_bytes().as_ref())?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads parameters written by `write_parameters` into a model with the same architecture.
+pub fn read_parameters<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    reader: impl Read,
+) -> Result<()> {
+    let mut reader = io::BufReader::new(reader);
+    let mut bytes = F::Bytes::default();
+
+    for (i, mut param) in model.parameters().enumerate() {
+        match reader.read_exact(bytes.as_mut()) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(Error::MalformedModelFile(format!(
+                    "Expected {} parameters but found only {i}",
+                    model.num_parameters()
+                )));
+            }
+            Err(err) => return Err(err.into()),
+        }
+        param.set_data(F::from_le_bytes(bytes));
+        bytes = F::Bytes::default();
+    }
+
+    if reader.read(bytes.as_mut())? != 0 {
+        return Err(Error::MalformedModelFile(
+            "Model file has extra unread bytes".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn activation_code(activation: Activation) -> u8 {
+    match activation {
+        Activation::Identity => 0,
+        Activation::Tanh => 1,
+        Activation::Relu => 2,
+        Activation::Sigmoid => 3,
+    }
+}
+
+fn activation_from_code(code: u8) -> Result<Activation> {
+    match code {
+        0 => Ok(Activation::Identity),
+        1 => Ok(Activation::Tanh),
+        2 => Ok(Activation::Relu),
+        3 => Ok(Activation::Sigmoid),
+        _ => Err(Error::MalformedModelFile(format!(
+            "Unknown activation code {code}"
+        ))),
+    }
+}
+
+/// Writes a header with the architecture of `model` followed by its parameters, see
+/// `write_parameters`.
+pub fn write_model<F: Float>(model: &MultiLayerPerceptron<F>, writer: impl Write) -> Result<()> {
+    write_model_with_metadata(model, "", writer)
+}
+
+/// Like `write_model`, but also stores `metadata` in the header, see `ModelHeader::metadata`.
+pub fn write_model_with_metadata<F: Float>(
+    model: &MultiLayerPerceptron<F>,
+    metadata: &str,
+    writer: impl Write,
+) -> Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+    let layer_sizes = model.layer_sizes();
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&[F::Bytes::default().as_ref().len() as u8])?;
+    writer.write_all(&(layer_sizes.len() as u32).to_le_bytes())?;
+    for size in layer_sizes {
+        writer.write_all(&(size as u64).to_le_bytes())?;
+    }
+    for activation in model.layer_activations() {
+        let activation = activation.unwrap_or(Activation::Identity);
+        writer.write_all(&[activation_code(activation)])?;
+    }
+    writer.write_all(&(metadata.len() as u64).to_le_bytes())?;
+    writer.write_all(metadata.as_bytes())?;
+
+    write_parameters(model, writer)
+}
+
+/// Reads the header of a file written by `write_model`, leaving `reader` at the parameters.
+pub fn read_header(mut reader: impl Read) -> Result<ModelHeader> {
+    fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        reader.read_exact(&mut bytes).map_err(|err| {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                Error::MalformedModelFile("Model file header is truncated".to_string())
+            } else {
+                err.into()
+            }
+        })?;
+        Ok(bytes)
+    }
+
+    if read_array(&mut reader)? != *MAGIC {
+        return Err(Error::MalformedModelFile(
+            "Model file has no header, it may only contain parameters".to_string(),
+        ));
+    }
+    let version = u32::from_le_bytes(read_array(&mut reader)?);
+    if version != VERSION {
+        return Err(Error::MalformedModelFile(format!(
+            "Unsupported model file version {version}"
+        )));
+    }
+
+    let [float_size] = read_array(&mut reader)?;
+    let num_layers = u32::from_le_bytes(read_array(&mut reader)?);
+    let layer_sizes = (0..num_layers)
+        .map(|_| {
+            let size = u64::from_le_bytes(read_array(&mut reader)?);
+            usize::try_from(size)
+                .map_err(|_| Error::MalformedModelFile(format!("Layer size {size} is too large")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if layer_sizes.len() < 2 {
+        return Err(Error::MalformedModelFile(
+            "Model needs at least an input and an output layer".to_string(),
+        ));
+    }
+
+    let activations = (1..layer_sizes.len())
+        .map(|_| activation_from_code(read_array::<1>(&mut reader)?[0]))
+        .collect::<Result<Vec<_>>>()?;
+
+    let metadata_len = u64::from_le_bytes(read_array(&mut reader)?);
+    let mut metadata = Vec::new();
+    reader
+        .by_ref()
+        .take(metadata_len)
+        .read_to_end(&mut metadata)?;
+    if metadata.len() as u64 != metadata_len {
+        return Err(Error::MalformedModelFile(
+            "Model file header is truncated".to_string(),
+        ));
+    }
+    let metadata = String::from_utf8(metadata).map_err(|_| {
+        Error::MalformedModelFile("Model file metadata is not valid UTF-8".to_string())
+    })?;
+
+    let header = ModelHeader {
+        float_size: float_size as usize,
+        layer_sizes,
+        activations,
+        metadata,
+    };
+    header.num_parameters()?;
+
+    Ok(header)
+}
+
+/// Reads a model written by `write_model`, creating it with the architecture from the header.
+pub fn read_model<F: Float>(reader: impl Read) -> Result<MultiLayerPerceptron<F>> {
+    let mut reader = io::BufReader::new(reader);
+    let header = read_header(&mut reader)?;
+
+    let float_size = F::Bytes::default().as_ref().len();
+    if header.float_size != float_size {
+        return Err(Error::MalformedModelFile(format!(
+            "Model has {}-byte parameters but {float_size}-byte parameters were requested",
+            header.float_size
+        )));
+    }
+
+    // Read the parameters before building the model, so a header that claims more parameters
+    // than the file contains doesn't allocate a huge model first
+    let num_parameters = header.num_parameters()?;
+    let mut bytes = Vec::new();
+    reader
+        .by_ref()
+        .take((num_parameters * float_size) as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() != num_parameters * float_size {
+        return Err(Error::MalformedModelFile(format!(
+            "Expected {num_parameters} parameters but found only {}",
+            bytes.len() / float_size
+        )));
+    }
+    if reader.read(&mut [0])? != 0 {
+        return Err(Error::MalformedModelFile(
+            "Model file has extra unread bytes".to_string(),
+        ));
+    }
+    let parameters = bytes.chunks_exact(float_size).map(|chunk| {
+        let mut bytes = F::Bytes::default();
+        bytes.as_mut().copy_from_slice(chunk);
+        F::from_le_bytes(bytes)
+    });
+
+    let model = header.build_model(&mut rand::rng())?;
+    for (mut param, data) in model.parameters().zip(parameters) {
+        param.set_data(data);
+    }
+
+    Ok(model)
+}
+
+/// Like `read_model`, but also accepts files written by `write_parameters` without a header. Their
+/// parameters are read into the model returned by `fallback`, which must have the architecture
+/// they were written with. Saving the model with `write_model` converts the file.
+pub fn read_model_or_parameters<F: Float>(
+    reader: impl Read,
+    fallback: impl FnOnce() -> MultiLayerPerceptron<F>,
+) -> Result<MultiLayerPerceptron<F>> {
+    let mut reader = io::BufReader::new(reader);
+    let mut magic = Vec::new();
+    reader
+        .by_ref()
+        .take(MAGIC.len() as u64)
+        .read_to_end(&mut magic)?;
+    let reader = magic.as_slice().chain(reader);
+
+    if magic == MAGIC {
+        read_model(reader)
+    } else {
+        let model = fallback();
+        read_parameters(&model, reader)?;
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{concat::Concat, layer::Layer, residual::Residual, sequential::Sequential};
+
+    fn model() -> Sequential {
+        let mut model = Sequential::default();
+        model.push(Concat::dense(Layer::new(4, 3)));
+        model.push(Residual::new(Layer::new(7, 7)));
+        model.push(Layer::new(7, 2));
+        model
+    }
+
+    #[test]
+    fn test() {
+        let a = model();
+        let b = model();
+
+        let mut bytes = Vec::new();
+        write_parameters(&a, &mut bytes).unwrap();
+        assert_eq!(bytes.len(), a.parameters().count() * 8);
+
+        read_parameters(&b, bytes.as_slice()).unwrap();
+        for (x, y) in a.parameters().zip(b.parameters()) {
+            assert_eq!(x.data(), y.data());
+        }
+
+        bytes.push(0);
+        assert!(matches!(
+            read_parameters(&b, bytes.as_slice()),
+            Err(Error::MalformedModelFile(_))
+        ));
+        assert!(matches!(
+            read_parameters(&b, &bytes[..16]),
+            Err(Error::MalformedModelFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_f32() {
+        let a = MultiLayerPerceptron::<f32>::new(4, &[3], 2);
+        let b = MultiLayerPerceptron::<f32>::new(4, &[3], 2);
+
+        let mut bytes = Vec::new();
+        write_parameters(&a, &mut bytes).unwrap();
+        assert_eq!(bytes.len(), a.num_parameters() * 4);
+
+        read_parameters(&b, bytes.as_slice()).unwrap();
+        for (x, y) in a.parameters().zip(b.parameters()) {
+            assert_eq!(x.data(), y.data());
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let a: MultiLayerPerceptron = MultiLayerPerceptron::new(4, &[3, 5], 2);
+
+        let mut bytes = Vec::new();
+        write_model(&a, &mut bytes).unwrap();
+
+        let header = read_header(bytes.as_slice()).unwrap();
+        assert_eq!(
+            header,
+            ModelHeader {
+                float_size: 8,
+                layer_sizes: vec![4, 3, 5, 2],
+                activations: vec![Activation::Tanh; 3],
+                metadata: String::new(),
+            }
+        );
+        assert_eq!(header.num_parameters().unwrap(), a.num_parameters());
+        assert_eq!(header.summary().unwrap(), a.summary());
+
+        let b: MultiLayerPerceptron = read_model(bytes.as_slice()).unwrap();
+        assert_eq!(b.layer_sizes(), a.layer_sizes());
+        for (x, y) in a.parameters().zip(b.parameters()) {
+            assert_eq!(x.data(), y.data());
+        }
+
+        assert!(matches!(
+            read_model::<f32>(bytes.as_slice()),
+            Err(Error::MalformedModelFile(_))
+        ));
+        assert!(matches!(
+            read_header(&bytes[8..]),
+            Err(Error::MalformedModelFile(_))
+        ));
+        assert!(matches!(
+            read_header(&bytes[..10]),
+            Err(Error::MalformedModelFile(_))
+        ));
+
+        let mut other_version = bytes.clone();
+        other_version[8..12].copy_from_slice(&1u32.to_le_bytes());
+        assert!(matches!(
+            read_header(other_version.as_slice()),
+            Err(Error::MalformedModelFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_malformed_header() {
+        fn header(layer_sizes: &[u64]) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            bytes.extend_from_slice(MAGIC);
+            bytes.extend_from_slice(&VERSION.to_le_bytes());
+            bytes.push(8);
+            bytes.extend_from_slice(&(layer_sizes.len() as u32).to_le_bytes());
+            for size in layer_sizes {
+                bytes.extend_from_slice(&size.to_le_bytes());
+            }
+            bytes.extend(std::iter::repeat_n(1, layer_sizes.len() - 1));
+            bytes.extend_from_slice(&0u64.to_le_bytes());
+            bytes
+        }
+
+        assert_eq!(
+            read_header(header(&[4, 2]).as_slice())
+                .unwrap()
+                .num_parameters()
+                .unwrap(),
+            10
+        );
+        for layer_sizes in [
+            &[4, 0][..],
+            &[u64::MAX, 2],
+            &[1 << 40, 1 << 40],
+            &[1 << 20, 1 << 20],
+            &[16384, 16383],
+        ] {
+            assert!(matches!(
+                read_header(header(layer_sizes).as_slice()),
+                Err(Error::MalformedModelFile(_))
+            ));
+        }
+
+        // A valid header without the parameters it declares
+        assert!(matches!(
+            read_model::<f64>(header(&[1000, 1000]).as_slice()),
+            Err(Error::MalformedModelFile(_))
+        ));
+
+        // Far more layers than the file contains
+        let mut bytes = header(&[4, 2]);
+        bytes[13..17].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read_header(bytes.as_slice()),
+            Err(Error::MalformedModelFile(_))
+        ));
+
+        let invalid = ModelHeader {
+            float_size: 8,
+            layer_sizes: vec![4, 3, 2],
+            activations: vec![Activation::Tanh],
+            metadata: String::new(),
+        };
+        assert!(matches!(
+            invalid.build_model::<f64>(&mut rand::rng()),
+            Err(Error::MalformedModelFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_headerless() {
+        let a: MultiLayerPerceptron = MultiLayerPerceptron::new(4, &[3], 2);
+        let fallback = || MultiLayerPerceptron::new(4, &[3], 2);
+
+        let mut with_header = Vec::new();
+        write_model(&a, &mut with_header).unwrap();
+        let mut without_header = Vec::new();
+        write_parameters(&a, &mut without_header).unwrap();
+
+        for bytes in [with_header, without_header] {
+            let b = read_model_or_parameters(bytes.as_slice(), fallback).unwrap();
+            for (x, y) in a.parameters().zip(b.parameters()) {
+                assert_eq!(x.data(), y.data());
+            }
+        }
+
+        assert!(matches!(
+            read_model_or_parameters(&[0u8; 4][..], fallback),
+            Err(Error::MalformedModelFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_metadata() {
+        let a: MultiLayerPerceptron = MultiLayerPerceptron::from_layers(
+            4,
+            &[(3, Activation::Relu), (2, Activation::Sigmoid)],
+            Initializer::Xavier,
+            &mut rand::rng(),
+        );
+
+        let mut bytes = Vec::new();
+        write_model_with_metadata(&a, "seed = 7\n", &mut bytes).unwrap();
+
+        let header = read_header(bytes.as_slice()).unwrap();
+        assert_eq!(
+            header.activations,
+            vec![Activation::Relu, Activation::Sigmoid]
+        );
+        assert_eq!(header.metadata, "seed = 7\n");
+
+        let b: MultiLayerPerceptron = read_model(bytes.as_slice()).unwrap();
+        assert_eq!(b.layer_activations(), a.layer_activations());
+        for (x, y) in a.parameters().zip(b.parameters()) {
+            assert_eq!(x.data(), y.data());
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{
+    activation::Activation,
+    error::{Error, Result},
+    float::Float,
+    value::Value,
+};
+
+/// A building block of a model that maps a slice of activations to a new set of activations.
+pub trait Module<F: Float = f64> {
+    fn forward(&self, inputs: &[Value<F>]) -> Vec<Value<F>>;
+
+    /// Like `forward`, but returns an error instead of panicking if the number of inputs does not
+    /// match what the module expects.
+    fn try_forward(&self, inputs: &[Value<F>]) -> Result<Vec<Value<F>>> {
+        if let Some(expected) = self.input_size()
+            && expected != inputs.len()
+        {
+            return Err(Error::DimensionMismatch {
+                expected,
+                actual: inputs.len(),
+            });
+        }
+        Ok(self.forward(inputs))
+    }
+
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_>;
+
+    fn name(&self) -> &str;
+
+    /// Number of inputs the module expects, if it is fixed.
+    fn input_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Number of activations produced for `input_size` inputs.
+    fn output_size(&self, input_size: usize) -> usize {
+        input_size
+    }
+
+    fn num_parameters(&self) -> usize {
+        self.parameters().count()
+    }
+
+    /// Resets the gradients of all parameters to 0.
+    fn zero_grad(&self) {
+        for mut param in self.parameters() {
+            param.zero_grad();
+        }
+    }
+
+    /// Activation function applied to the output, if any.
+    fn activation(&self) -> Option<Activation> {
+        None
+    }
+
+    /// Switches between training and evaluation behaviour. Only modules that behave differently
+    /// during training (e.g. dropout) need to override this.
+    fn set_training(&mut self, _training: bool) {}
+
+    fn train(&mut self) {
+        self.set_training(true);
+    }
+
+    fn eval(&mut self) {
+        self.set_training(false);
+    }
+}
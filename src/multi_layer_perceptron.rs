@@ -1,36 +1,163 @@
-use std::iter;
+use rand::Rng;
+use std::{fmt, iter};
 
-use crate::{layer::Layer, value::Value};
+use crate::{
+    activation::Activation,
+    error::Result,
+    float::Float,
+    initializer::Initializer,
+    layer::Layer,
+    module::Module,
+    sequential::Sequential,
+    value::{Value, no_grad},
+};
 
-pub struct MultiLayerPerceptron {
-    layers: Vec<Layer>,
+/// Stack of dense layers, `tanh` unless created with `from_layers`. A convenience wrapper around `Sequential`.
+#[derive(Debug)]
+pub struct MultiLayerPerceptron<F: Float = f64> {
+    num_inputs: usize,
+    sequential: Sequential<F>,
 }
 
-impl MultiLayerPerceptron {
+impl<F: Float> MultiLayerPerceptron<F> {
     pub fn new(num_inputs: usize, hidden_layer_sizes: &[usize], num_outputs: usize) -> Self {
-        let mut layers = Vec::with_capacity(hidden_layer_sizes.len() + 1);
+        Self::with_rng(
+            num_inputs,
+            hidden_layer_sizes,
+            num_outputs,
+            &mut rand::rng(),
+        )
+    }
 
-        let mut last_size = num_inputs;
-        for layer_size in hidden_layer_sizes
+    /// Like `new`, but samples the initial weights from `rng`.
+    pub fn with_rng(
+        num_inputs: usize,
+        hidden_layer_sizes: &[usize],
+        num_outputs: usize,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let layers = hidden_layer_sizes
             .iter()
             .copied()
             .chain(iter::once(num_outputs))
-        {
-            layers.push(Layer::new(last_size, layer_size));
+            .map(|size| (size, Activation::Tanh))
+            .collect::<Vec<_>>();
+        Self::from_layers(num_inputs, &layers, Initializer::Uniform, rng)
+    }
+
+    /// Creates a perceptron with one dense layer per `(size, activation)` in `layers`, the last
+    /// being the output layer.
+    pub fn from_layers(
+        num_inputs: usize,
+        layers: &[(usize, Activation)],
+        initializer: Initializer,
+        rng: &mut impl Rng,
+    ) -> Self {
+        assert!(!layers.is_empty(), "Expected at least an output layer");
+        let mut sequential = Sequential::default();
+
+        let mut last_size = num_inputs;
+        for &(layer_size, activation) in layers {
+            sequential.push(Layer::with_initializer(
+                last_size,
+                layer_size,
+                activation,
+                initializer,
+                rng,
+            ));
             last_size = layer_size;
         }
 
-        MultiLayerPerceptron { layers }
+        MultiLayerPerceptron {
+            num_inputs,
+            sequential,
+        }
+    }
+
+    pub fn into_sequential(self) -> Sequential<F> {
+        self.sequential
+    }
+
+    /// Sizes of all layers, starting with the number of inputs and ending with the number of
+    /// outputs.
+    pub fn layer_sizes(&self) -> Vec<usize> {
+        iter::once(self.num_inputs)
+            .chain(
+                self.sequential
+                    .modules()
+                    .iter()
+                    .scan(self.num_inputs, |size, module| {
+                        *size = module.output_size(*size);
+                        Some(*size)
+                    }),
+            )
+            .collect()
+    }
+
+    /// Number of parameters of every layer.
+    pub fn layer_num_parameters(&self) -> Vec<usize> {
+        self.sequential
+            .modules()
+            .iter()
+            .map(|module| module.num_parameters())
+            .collect()
+    }
+
+    /// Runs `forward` on plain floats without building a computation graph, e.g. for inference.
+    pub fn predict(&self, input: &[F]) -> Vec<F> {
+        no_grad(|| {
+            let input = input.iter().map(|&x| Value::new(x)).collect::<Vec<_>>();
+            self.forward(&input).iter().map(Value::data).collect()
+        })
     }
 
-    pub fn forward(&self, inputs: &[Value]) -> Vec<Value> {
-        self.layers
+    /// Activation of every layer.
+    pub fn layer_activations(&self) -> Vec<Option<Activation>> {
+        self.sequential
+            .modules()
             .iter()
-            .fold(Vec::from(inputs), |acc, layer| layer.forward(&acc))
+            .map(|module| module.activation())
+            .collect()
     }
 
-    pub fn parameters(&self) -> impl Iterator<Item = Value> {
-        self.layers.iter().flat_map(|layer| layer.paramters())
+    pub fn summary(&self) -> String {
+        self.sequential.summary(self.num_inputs)
+    }
+}
+
+impl<F: Float> Module<F> for MultiLayerPerceptron<F> {
+    fn forward(&self, inputs: &[Value<F>]) -> Vec<Value<F>> {
+        self.sequential.forward(inputs)
+    }
+
+    fn try_forward(&self, inputs: &[Value<F>]) -> Result<Vec<Value<F>>> {
+        self.sequential.try_forward(inputs)
+    }
+
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_> {
+        self.sequential.parameters()
+    }
+
+    fn name(&self) -> &str {
+        "MultiLayerPerceptron"
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.num_inputs)
+    }
+
+    fn output_size(&self, _input_size: usize) -> usize {
+        self.sequential.output_size(self.num_inputs)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.sequential.set_training(training);
+    }
+}
+
+impl<F: Float> fmt::Display for MultiLayerPerceptron<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.summary())
     }
 }
 
@@ -40,7 +167,61 @@ mod test {
 
     #[test]
     fn test() {
-        let mlp = MultiLayerPerceptron::new(10, &[9, 5, 10], 1);
+        let mlp: MultiLayerPerceptron = MultiLayerPerceptron::new(10, &[9, 5, 10], 1);
         assert_eq!(mlp.parameters().count(), 220);
     }
+
+    #[test]
+    fn test_predict() {
+        let mlp: MultiLayerPerceptron = MultiLayerPerceptron::new(3, &[4], 2);
+        let input = [0.5, -1.0, 2.0];
+
+        let expected = mlp
+            .forward(&input.map(Value::new))
+            .iter()
+            .map(Value::data)
+            .collect::<Vec<_>>();
+        assert_eq!(mlp.predict(&input), expected);
+    }
+
+    #[test]
+    fn test_introspection() {
+        let mlp: MultiLayerPerceptron = MultiLayerPerceptron::new(10, &[9, 5, 10], 1);
+        assert_eq!(mlp.layer_sizes(), vec![10, 9, 5, 10, 1]);
+        assert_eq!(mlp.layer_num_parameters(), vec![99, 50, 60, 11]);
+        assert_eq!(mlp.num_parameters(), 220);
+        assert_eq!(mlp.layer_activations(), vec![Some(Activation::Tanh); 4]);
+
+        let summary = mlp.summary();
+        assert_eq!(summary.lines().count(), 8);
+        assert!(summary.ends_with("Total params: 220"));
+    }
+
+    #[test]
+    fn test_from_layers() {
+        let mlp: MultiLayerPerceptron = MultiLayerPerceptron::from_layers(
+            6,
+            &[(4, Activation::Relu), (2, Activation::Identity)],
+            Initializer::He,
+            &mut rand::rng(),
+        );
+        assert_eq!(mlp.layer_sizes(), vec![6, 4, 2]);
+        assert_eq!(
+            mlp.layer_activations(),
+            vec![Some(Activation::Relu), Some(Activation::Identity)]
+        );
+
+        let parameters = mlp.parameters().collect::<Vec<_>>();
+        let (first, second) = parameters.split_at(4 * 7);
+        assert!(
+            first
+                .iter()
+                .all(|p| p.data().abs() <= Initializer::He.limit(6, 4))
+        );
+        assert!(
+            second
+                .iter()
+                .all(|p| p.data().abs() <= Initializer::He.limit(4, 2))
+        );
+    }
 }
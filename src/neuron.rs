@@ -1,42 +1,98 @@
-use crate::value::Value;
+use crate::{
+    activation::Activation,
+    error::{Error, Result},
+    float::Float,
+    initializer::Initializer,
+    value::Value,
+};
 use rand::{distr::Uniform, prelude::*};
-use std::iter;
+use std::{fmt, iter};
 
-pub struct Neuron {
-    weights: Vec<Value>,
-    bias: Value,
+#[derive(Debug)]
+pub struct Neuron<F: Float = f64> {
+    weights: Vec<Value<F>>,
+    bias: Value<F>,
+    activation: Activation,
 }
 
-impl Neuron {
+impl<F: Float> Neuron<F> {
     pub fn new(num_inputs: usize) -> Self {
-        let rng = rand::rng();
-        let dist = Uniform::new_inclusive(-1.0, 1.0).unwrap();
+        Self::with_activation(num_inputs, Activation::Tanh)
+    }
+
+    pub fn with_activation(num_inputs: usize, activation: Activation) -> Self {
+        Self::with_rng(num_inputs, activation, &mut rand::rng())
+    }
+
+    /// Like `with_activation`, but samples the initial weights from `rng`, e.g. a seeded one for
+    /// reproducible training.
+    pub fn with_rng(num_inputs: usize, activation: Activation, rng: &mut impl Rng) -> Self {
+        let dist = Initializer::Uniform.distribution(num_inputs, 1);
+        Self::with_distribution(num_inputs, activation, dist, rng)
+    }
 
+    pub(crate) fn with_distribution(
+        num_inputs: usize,
+        activation: Activation,
+        dist: Uniform<f64>,
+        rng: &mut impl Rng,
+    ) -> Self {
         Self {
             weights: dist
                 .sample_iter(rng)
                 .take(num_inputs)
-                .map(Value::new)
+                .map(|weight| Value::new(F::from_f64(weight)))
                 .collect::<Vec<_>>(),
-            bias: Value::new(0.0),
+            bias: Value::new(F::ZERO),
+            activation,
         }
     }
 
-    pub fn forward(&self, activations: &[Value]) -> Value {
+    pub fn num_inputs(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn activation(&self) -> Activation {
+        self.activation
+    }
+
+    pub fn forward(&self, activations: &[Value<F>]) -> Value<F> {
         assert_eq!(activations.len(), self.weights.len());
-        activations
+        let pre_activation = activations
             .iter()
             .zip(&self.weights)
             .fold(self.bias.clone(), |acc, (activation, weight)| {
                 &acc + &(activation * weight)
-            })
-            .tanh()
+            });
+        self.activation.apply(&pre_activation)
+    }
+
+    /// Like `forward`, but returns an error instead of panicking on an input size mismatch.
+    pub fn try_forward(&self, activations: &[Value<F>]) -> Result<Value<F>> {
+        if activations.len() != self.weights.len() {
+            return Err(Error::DimensionMismatch {
+                expected: self.weights.len(),
+                actual: activations.len(),
+            });
+        }
+        Ok(self.forward(activations))
     }
 
-    pub fn parameters(&self) -> impl Iterator<Item = Value> {
+    pub fn parameters(&self) -> impl Iterator<Item = Value<F>> {
         self.weights
             .iter()
             .cloned()
             .chain(iter::once(self.bias.clone()))
     }
 }
+
+impl<F: Float> fmt::Display for Neuron<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Neuron({} inputs, {})",
+            self.num_inputs(),
+            self.activation
+        )
+    }
+}
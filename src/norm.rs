@@ -0,0 +1,75 @@
+use crate::{float::Float, module::Module, value::Value};
+
+/// Layer normalization: normalizes the inputs to zero mean and unit variance, then applies a
+/// learned per-feature gain and bias.
+pub struct LayerNorm<F: Float = f64> {
+    gain: Vec<Value<F>>,
+    bias: Vec<Value<F>>,
+    epsilon: F,
+}
+
+impl<F: Float> LayerNorm<F> {
+    pub fn new(size: usize) -> Self {
+        Self {
+            gain: (0..size).map(|_| Value::new(F::ONE)).collect(),
+            bias: (0..size).map(|_| Value::new(F::ZERO)).collect(),
+            epsilon: F::from_f64(1e-5),
+        }
+    }
+}
+
+impl<F: Float> Module<F> for LayerNorm<F> {
+    fn forward(&self, inputs: &[Value<F>]) -> Vec<Value<F>> {
+        assert_eq!(inputs.len(), self.gain.len());
+
+        let n = Value::new(F::from_f64(inputs.len() as f64));
+        let mean = &inputs
+            .iter()
+            .fold(Value::new(F::ZERO), |acc, input| &acc + input)
+            / &n;
+        let centered = inputs.iter().map(|input| input - &mean).collect::<Vec<_>>();
+        let variance = &centered
+            .iter()
+            .fold(Value::new(F::ZERO), |acc, x| &acc + &(x * x))
+            / &n;
+        let inv_std = (&variance + &Value::new(self.epsilon)).powf(F::from_f64(-0.5));
+
+        centered
+            .iter()
+            .zip(self.gain.iter().zip(&self.bias))
+            .map(|(x, (gain, bias))| &(&(x * &inv_std) * gain) + bias)
+            .collect()
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        Some(self.gain.len())
+    }
+
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_> {
+        Box::new(self.gain.iter().chain(&self.bias).cloned())
+    }
+
+    fn name(&self) -> &str {
+        "LayerNorm"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let norm = LayerNorm::new(4);
+        let output = norm.forward(&[1.0, 2.0, 3.0, 6.0].map(Value::new));
+
+        let mean = output.iter().map(Value::data).sum::<f64>() / 4.0;
+        let variance = output
+            .iter()
+            .map(|x| (x.data() - mean).powi(2))
+            .sum::<f64>()
+            / 4.0;
+        assert!(mean.abs() < 1e-9);
+        assert!((variance - 1.0).abs() < 1e-4);
+    }
+}
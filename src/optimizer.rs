@@ -0,0 +1,139 @@
+use crate::{float::Float, value::Value};
+
+/// Updates parameters from their gradients, e.g. after `Value::backward` on the loss.
+///
+/// Optimizers with state keep one entry per parameter, so `step` must always be called with the
+/// parameters of the same model in the same order, as returned by `Module::parameters`.
+pub trait Optimizer<F: Float = f64> {
+    fn step(&mut self, parameters: &[Value<F>], learning_rate: f64);
+}
+
+/// Plain gradient descent, the same update as `training::apply_gradients`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sgd;
+
+impl<F: Float> Optimizer<F> for Sgd {
+    fn step(&mut self, parameters: &[Value<F>], learning_rate: f64) {
+        for param in parameters {
+            let mut param = param.clone();
+            param.set_data(param.data() - param.grad() * F::from_f64(learning_rate));
+        }
+    }
+}
+
+/// Gradient descent with momentum, which moves along an exponentially decaying sum of past
+/// gradients.
+#[derive(Clone, Debug)]
+pub struct Momentum {
+    momentum: f64,
+    velocity: Vec<f64>,
+}
+
+impl Momentum {
+    pub fn new(momentum: f64) -> Self {
+        assert!((0.0..1.0).contains(&momentum), "Momentum must be in [0, 1)");
+        Self {
+            momentum,
+            velocity: Vec::new(),
+        }
+    }
+}
+
+impl<F: Float> Optimizer<F> for Momentum {
+    fn step(&mut self, parameters: &[Value<F>], learning_rate: f64) {
+        self.velocity.resize(parameters.len(), 0.0);
+        for (param, velocity) in parameters.iter().zip(&mut self.velocity) {
+            *velocity = self.momentum * *velocity + param.grad().to_f64();
+            let mut param = param.clone();
+            param.set_data(param.data() - F::from_f64(learning_rate * *velocity));
+        }
+    }
+}
+
+/// Adam, which scales the step of every parameter by running estimates of the first and second
+/// moments of its gradient.
+#[derive(Clone, Debug)]
+pub struct Adam {
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    steps: i32,
+    first_moments: Vec<f64>,
+    second_moments: Vec<f64>,
+}
+
+impl Adam {
+    pub fn new() -> Self {
+        Self::with_betas(0.9, 0.999)
+    }
+
+    pub fn with_betas(beta1: f64, beta2: f64) -> Self {
+        Self {
+            beta1,
+            beta2,
+            epsilon: 1e-8,
+            steps: 0,
+            first_moments: Vec::new(),
+            second_moments: Vec::new(),
+        }
+    }
+}
+
+impl Default for Adam {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Float> Optimizer<F> for Adam {
+    fn step(&mut self, parameters: &[Value<F>], learning_rate: f64) {
+        self.first_moments.resize(parameters.len(), 0.0);
+        self.second_moments.resize(parameters.len(), 0.0);
+        self.steps += 1;
+
+        let first_correction = 1.0 - self.beta1.powi(self.steps);
+        let second_correction = 1.0 - self.beta2.powi(self.steps);
+
+        for ((param, m), v) in parameters
+            .iter()
+            .zip(&mut self.first_moments)
+            .zip(&mut self.second_moments)
+        {
+            let grad = param.grad().to_f64();
+            *m = self.beta1 * *m + (1.0 - self.beta1) * grad;
+            *v = self.beta2 * *v + (1.0 - self.beta2) * grad * grad;
+
+            let update = learning_rate * (*m / first_correction)
+                / ((*v / second_correction).sqrt() + self.epsilon);
+            let mut param = param.clone();
+            param.set_data(param.data() - F::from_f64(update));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Minimizes (x - 3)^2 starting from 0.
+    fn minimize(optimizer: &mut impl Optimizer, learning_rate: f64, steps: usize) -> f64 {
+        let x = Value::new(0.0);
+        for _ in 0..steps {
+            let mut loss = (&x - &Value::new(3.0)).powf(2.0);
+            loss.backward();
+            optimizer.step(std::slice::from_ref(&x), learning_rate);
+        }
+        x.data()
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(minimize(&mut Sgd, 0.25, 1), 1.5);
+        assert!((minimize(&mut Sgd, 0.1, 100) - 3.0).abs() < 1e-6);
+        assert!((minimize(&mut Momentum::new(0.5), 0.1, 100) - 3.0).abs() < 1e-6);
+        assert!((minimize(&mut Adam::new(), 0.1, 500) - 3.0).abs() < 1e-3);
+
+        // The first Adam step moves every parameter by about the learning rate
+        assert!((minimize(&mut Adam::new(), 0.1, 1) - 0.1).abs() < 1e-6);
+    }
+}
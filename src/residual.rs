@@ -0,0 +1,114 @@
+use crate::{
+    error::{Error, Result},
+    float::Float,
+    module::Module,
+    value::Value,
+};
+
+/// Skip connection that adds the input of a module to its output (ResNet-style). If the inner
+/// module changes the number of activations, a projection module can be used to map the input to
+/// the output size before the addition.
+pub struct Residual<F: Float = f64> {
+    inner: Box<dyn Module<F>>,
+    projection: Option<Box<dyn Module<F>>>,
+}
+
+impl<F: Float> Residual<F> {
+    pub fn new(inner: impl Module<F> + 'static) -> Self {
+        Self {
+            inner: Box::new(inner),
+            projection: None,
+        }
+    }
+
+    pub fn with_projection(
+        inner: impl Module<F> + 'static,
+        projection: impl Module<F> + 'static,
+    ) -> Self {
+        Self {
+            inner: Box::new(inner),
+            projection: Some(Box::new(projection)),
+        }
+    }
+}
+
+impl<F: Float> Module<F> for Residual<F> {
+    fn forward(&self, inputs: &[Value<F>]) -> Vec<Value<F>> {
+        let output = self.inner.forward(inputs);
+        let shortcut = match &self.projection {
+            Some(projection) => projection.forward(inputs),
+            None => Vec::from(inputs),
+        };
+        assert_eq!(output.len(), shortcut.len());
+
+        output.iter().zip(&shortcut).map(|(o, s)| o + s).collect()
+    }
+
+    fn try_forward(&self, inputs: &[Value<F>]) -> Result<Vec<Value<F>>> {
+        let output = self.inner.try_forward(inputs)?;
+        let shortcut = match &self.projection {
+            Some(projection) => projection.try_forward(inputs)?,
+            None => Vec::from(inputs),
+        };
+        if output.len() != shortcut.len() {
+            return Err(Error::DimensionMismatch {
+                expected: shortcut.len(),
+                actual: output.len(),
+            });
+        }
+
+        Ok(output.iter().zip(&shortcut).map(|(o, s)| o + s).collect())
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        self.inner.input_size()
+    }
+
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_> {
+        Box::new(
+            self.inner.parameters().chain(
+                self.projection
+                    .iter()
+                    .flat_map(|projection| projection.parameters()),
+            ),
+        )
+    }
+
+    fn name(&self) -> &str {
+        "Residual"
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        self.inner.output_size(input_size)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.inner.set_training(training);
+        if let Some(projection) = &mut self.projection {
+            projection.set_training(training);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{activation::Activation, layer::Layer};
+
+    #[test]
+    fn test() {
+        let inner = Layer::with_activation(3, 3, Activation::Identity);
+        let expected = inner.forward(&[1.0, 2.0, 3.0].map(Value::new));
+        let residual = Residual::new(inner);
+
+        let output = residual.forward(&[1.0, 2.0, 3.0].map(Value::new));
+        for (i, (o, e)) in output.iter().zip(&expected).enumerate() {
+            assert_eq!(o.data(), e.data() + (i + 1) as f64);
+        }
+        assert_eq!(residual.parameters().count(), 12);
+
+        let projected = Residual::with_projection(Layer::new(3, 2), Layer::new(3, 2));
+        assert_eq!(projected.forward(&[1.0, 2.0, 3.0].map(Value::new)).len(), 2);
+        assert_eq!(projected.parameters().count(), 16);
+    }
+}
@@ -0,0 +1,234 @@
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+use crate::{
+    error::{Error, Result},
+    float::Float,
+    module::Module,
+    value::Value,
+};
+
+/// Computes the gradient of the output for `class` with respect to every input value, i.e. how
+/// sensitive the prediction is to each pixel. Like all saliency methods, this backpropagates
+/// through the model and overwrites the gradients of its parameters.
+pub fn vanilla_gradient<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    input: &[F],
+    class: usize,
+) -> Vec<F> {
+    try_vanilla_gradient(model, input, class).unwrap()
+}
+
+/// Like `vanilla_gradient`, but returns an error if `input` doesn't match the model or `class` is
+/// out of range.
+pub fn try_vanilla_gradient<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    input: &[F],
+    class: usize,
+) -> Result<Vec<F>> {
+    let inputs = input.iter().map(|&x| Value::new(x)).collect::<Vec<_>>();
+    let output = model.try_forward(&inputs)?;
+    let mut class_output = output.get(class).cloned().ok_or(Error::InvalidClass {
+        class,
+        num_classes: output.len(),
+    })?;
+    class_output.try_backward()?;
+
+    Ok(inputs.iter().map(Value::grad).collect())
+}
+
+/// Multiplies the vanilla gradient with the input, so only pixels that are present contribute.
+pub fn gradient_times_input<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    input: &[F],
+    class: usize,
+) -> Vec<F> {
+    try_gradient_times_input(model, input, class).unwrap()
+}
+
+pub fn try_gradient_times_input<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    input: &[F],
+    class: usize,
+) -> Result<Vec<F>> {
+    let gradient = try_vanilla_gradient(model, input, class)?;
+    Ok(gradient.iter().zip(input).map(|(&g, &x)| g * x).collect())
+}
+
+/// Averages the vanilla gradient over `samples` copies of the input with Gaussian noise of
+/// standard deviation `noise_std` added, which reduces the visual noise of the map.
+pub fn smooth_grad<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    input: &[F],
+    class: usize,
+    samples: usize,
+    noise_std: f64,
+    rng: &mut impl Rng,
+) -> Vec<F> {
+    try_smooth_grad(model, input, class, samples, noise_std, rng).unwrap()
+}
+
+pub fn try_smooth_grad<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    input: &[F],
+    class: usize,
+    samples: usize,
+    noise_std: f64,
+    rng: &mut impl Rng,
+) -> Result<Vec<F>> {
+    if samples == 0 {
+        return Err(Error::InvalidArgument(
+            "SmoothGrad needs at least one sample".to_string(),
+        ));
+    }
+    if !(noise_std.is_finite() && noise_std >= 0.0) {
+        return Err(Error::InvalidArgument(format!(
+            "Noise standard deviation must be finite and non-negative, got {noise_std}"
+        )));
+    }
+    let noise = Normal::new(0.0, noise_std).unwrap();
+
+    let mut total = vec![F::ZERO; input.len()];
+    for _ in 0..samples {
+        let noisy_input = input
+            .iter()
+            .map(|&x| x + F::from_f64(noise.sample(rng)))
+            .collect::<Vec<_>>();
+        let gradient = try_vanilla_gradient(model, &noisy_input, class)?;
+        for (t, g) in total.iter_mut().zip(gradient) {
+            *t = *t + g;
+        }
+    }
+
+    let samples = F::from_f64(samples as f64);
+    Ok(total.into_iter().map(|t| t / samples).collect())
+}
+
+/// Integrates the gradient along the straight path from `baseline` (e.g. a black image) to the
+/// input with a Riemann sum of `steps` points, and multiplies it with the difference between
+/// them. The values sum up to approximately the difference of the class output between input and
+/// baseline.
+pub fn integrated_gradients<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    input: &[F],
+    baseline: &[F],
+    class: usize,
+    steps: usize,
+) -> Vec<F> {
+    try_integrated_gradients(model, input, baseline, class, steps).unwrap()
+}
+
+pub fn try_integrated_gradients<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    input: &[F],
+    baseline: &[F],
+    class: usize,
+    steps: usize,
+) -> Result<Vec<F>> {
+    if steps == 0 {
+        return Err(Error::InvalidArgument(
+            "Integrated gradients needs at least one step".to_string(),
+        ));
+    }
+    if baseline.len() != input.len() {
+        return Err(Error::DimensionMismatch {
+            expected: input.len(),
+            actual: baseline.len(),
+        });
+    }
+
+    let mut total = vec![F::ZERO; input.len()];
+    for step in 1..=steps {
+        let alpha = F::from_f64(step as f64 / steps as f64);
+        let point = input
+            .iter()
+            .zip(baseline)
+            .map(|(&x, &b)| b + alpha * (x - b))
+            .collect::<Vec<_>>();
+        let gradient = try_vanilla_gradient(model, &point, class)?;
+        for (t, g) in total.iter_mut().zip(gradient) {
+            *t = *t + g;
+        }
+    }
+
+    let steps = F::from_f64(steps as f64);
+    Ok(total
+        .into_iter()
+        .zip(input.iter().zip(baseline))
+        .map(|(t, (&x, &b))| (x - b) * t / steps)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{activation::Activation, layer::Layer};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn test_linear_model() {
+        // For a linear model every method reduces to the weights of the class
+        let model: Layer = Layer::with_activation(3, 2, Activation::Identity);
+        let weights = model.neurons()[1]
+            .parameters()
+            .take(3)
+            .map(|w| w.data())
+            .collect::<Vec<_>>();
+        let input = [0.5, -1.0, 2.0];
+
+        assert_eq!(vanilla_gradient(&model, &input, 1), weights);
+
+        let expected = weights
+            .iter()
+            .zip(input)
+            .map(|(w, x)| w * x)
+            .collect::<Vec<_>>();
+        assert_eq!(gradient_times_input(&model, &input, 1), expected);
+
+        let attributions = integrated_gradients(&model, &input, &[0.0; 3], 1, 4);
+        for (a, e) in attributions.iter().zip(&expected) {
+            assert!((a - e).abs() < 1e-12);
+        }
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let smoothed = smooth_grad(&model, &input, 1, 4, 0.1, &mut rng);
+        for (s, w) in smoothed.iter().zip(&weights) {
+            assert!((s - w).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let model: Layer = Layer::new(3, 2);
+        assert!(matches!(
+            try_vanilla_gradient(&model, &[0.0; 3], 2),
+            Err(Error::InvalidClass {
+                class: 2,
+                num_classes: 2
+            })
+        ));
+        assert!(matches!(
+            try_integrated_gradients(&model, &[0.0; 3], &[0.0; 2], 0, 4),
+            Err(Error::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            try_integrated_gradients(&model, &[0.0; 3], &[0.0; 3], 0, 0),
+            Err(Error::InvalidArgument(_))
+        ));
+
+        let mut rng = rand::rng();
+        assert!(matches!(
+            try_smooth_grad(&model, &[0.0; 3], 0, 0, 0.1, &mut rng),
+            Err(Error::InvalidArgument(_))
+        ));
+        for noise_std in [-0.1, f64::NAN] {
+            assert!(matches!(
+                try_smooth_grad(&model, &[0.0; 3], 0, 4, noise_std, &mut rng),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt::{self, Write};
+
+use crate::{error::Result, float::Float, module::Module, value::Value};
+
+/// Applies a list of modules one after another, feeding the output of each into the next.
+pub struct Sequential<F: Float = f64> {
+    modules: Vec<Box<dyn Module<F>>>,
+}
+
+impl<F: Float> Default for Sequential<F> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<F: Float> Sequential<F> {
+    pub fn new(modules: Vec<Box<dyn Module<F>>>) -> Self {
+        Self { modules }
+    }
+
+    pub fn push(&mut self, module: impl Module<F> + 'static) {
+        self.modules.push(Box::new(module));
+    }
+
+    pub fn modules(&self) -> &[Box<dyn Module<F>>] {
+        &self.modules
+    }
+
+    /// Renders a table with the output shape, parameter count and activation of every module
+    /// when fed `input_size` inputs.
+    pub fn summary(&self, input_size: usize) -> String {
+        const RULE_WIDTH: usize = 64;
+
+        let mut summary = String::new();
+        let mut size = input_size;
+
+        writeln!(
+            summary,
+            "{:<20}{:>14}{:>14}{:>16}",
+            "Layer (type)", "Output Shape", "Param #", "Activation"
+        )
+        .unwrap();
+        writeln!(summary, "{}", "=".repeat(RULE_WIDTH)).unwrap();
+
+        for (i, module) in self.modules.iter().enumerate() {
+            size = module.output_size(size);
+            let activation = module
+                .activation()
+                .map_or_else(|| "-".to_string(), |activation| activation.to_string());
+            writeln!(
+                summary,
+                "{:<20}{:>14}{:>14}{:>16}",
+                format!("{} ({i})", module.name()),
+                format!("({size})"),
+                module.num_parameters(),
+                activation
+            )
+            .unwrap();
+        }
+
+        writeln!(summary, "{}", "=".repeat(RULE_WIDTH)).unwrap();
+        write!(summary, "Total params: {}", self.num_parameters()).unwrap();
+
+        summary
+    }
+}
+
+impl<F: Float> fmt::Debug for Sequential<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.modules.iter().map(|module| module.name()))
+            .finish()
+    }
+}
+
+impl<F: Float> Module<F> for Sequential<F> {
+    fn forward(&self, inputs: &[Value<F>]) -> Vec<Value<F>> {
+        self.modules
+            .iter()
+            .fold(Vec::from(inputs), |acc, module| module.forward(&acc))
+    }
+
+    fn try_forward(&self, inputs: &[Value<F>]) -> Result<Vec<Value<F>>> {
+        self.modules
+            .iter()
+            .try_fold(Vec::from(inputs), |acc, module| module.try_forward(&acc))
+    }
+
+    fn parameters(&self) -> Box<dyn Iterator<Item = Value<F>> + '_> {
+        Box::new(self.modules.iter().flat_map(|module| module.parameters()))
+    }
+
+    fn name(&self) -> &str {
+        "Sequential"
+    }
+
+    fn input_size(&self) -> Option<usize> {
+        self.modules.first().and_then(|module| module.input_size())
+    }
+
+    fn output_size(&self, input_size: usize) -> usize {
+        self.modules
+            .iter()
+            .fold(input_size, |size, module| module.output_size(size))
+    }
+
+    fn set_training(&mut self, training: bool) {
+        for module in self.modules.iter_mut() {
+            module.set_training(training);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{activation::Activation, dropout::Dropout, layer::Layer, norm::LayerNorm};
+
+    #[test]
+    fn test() {
+        let mut sequential = Sequential::default();
+        sequential.push(Layer::with_activation(4, 3, Activation::Identity));
+        sequential.push(LayerNorm::new(3));
+        sequential.push(Activation::Relu);
+        sequential.push(Dropout::new(0.5));
+        sequential.push(Layer::new(3, 2));
+        sequential.eval();
+
+        assert_eq!(sequential.parameters().count(), 15 + 6 + 8);
+
+        let output = sequential.forward(&[1.0, 2.0, 3.0, 4.0].map(Value::new));
+        assert_eq!(output.len(), 2);
+    }
+}
@@ -1,13 +1,19 @@
-use crate::{multi_layer_perceptron::MultiLayerPerceptron, value::Value};
+use crate::{
+    error::{Error, Result},
+    float::Float,
+    module::Module,
+    optimizer::{Optimizer, Sgd},
+    value::{Value, no_grad},
+};
 use rand::{distr::Uniform, prelude::*};
 
-pub struct TrainingData {
-    pub input: Vec<f64>,
-    pub expected_output: Vec<f64>,
+pub struct TrainingData<F: Float = f64> {
+    pub input: Vec<F>,
+    pub expected_output: Vec<F>,
 }
 
-impl TrainingData {
-    pub fn new(input: Vec<f64>, expected_output: Vec<f64>) -> Self {
+impl<F: Float> TrainingData<F> {
+    pub fn new(input: Vec<F>, expected_output: Vec<F>) -> Self {
         Self {
             input,
             expected_output,
@@ -18,18 +24,118 @@ impl TrainingData {
 pub struct GradientDescentResult {
     pub avg_loss: f64,
     pub avg_accuracy: f64,
+    /// Whether the parameters were updated. `false` if the step was skipped because the loss was
+    /// not finite.
+    pub updated: bool,
 }
 
-pub fn gradient_descent<'a>(
-    model: &MultiLayerPerceptron,
-    training_data: impl Iterator<Item = &'a TrainingData>,
+/// Performs a single gradient descent step on the examples yielded by `training_data`. If the
+/// loss is not finite, the update is skipped instead of corrupting the parameters.
+///
+/// Panics on the other errors reported by `try_gradient_descent`.
+pub fn gradient_descent<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: impl Iterator<Item = &'a TrainingData<F>>,
     iteration: usize,
-    mut loss_function: impl FnMut(&[Value], &[f64]) -> Value,
-    mut accuracy_function: impl FnMut(&[Value], &[f64]) -> bool,
-    mut learning_rate: impl FnMut(usize) -> f64,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    learning_rate: impl FnMut(usize) -> f64,
 ) -> GradientDescentResult {
-    struct Acc {
-        total_loss: Value,
+    gradient_descent_step(
+        model,
+        training_data,
+        iteration,
+        loss_function,
+        accuracy_function,
+        learning_rate,
+        &mut Sgd,
+        true,
+    )
+    .unwrap()
+}
+
+/// Like `gradient_descent`, but returns an error if `training_data` is empty, an input does not
+/// match the model or the loss is not finite. The parameters are left untouched in that case.
+pub fn try_gradient_descent<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: impl Iterator<Item = &'a TrainingData<F>>,
+    iteration: usize,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    learning_rate: impl FnMut(usize) -> f64,
+) -> Result<GradientDescentResult> {
+    gradient_descent_step(
+        model,
+        training_data,
+        iteration,
+        loss_function,
+        accuracy_function,
+        learning_rate,
+        &mut Sgd,
+        false,
+    )
+}
+
+/// Like `gradient_descent`, but updates the parameters with `optimizer` instead of plain gradient
+/// descent. Use the same optimizer for every step, since it may keep state per parameter.
+pub fn gradient_descent_with_optimizer<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: impl Iterator<Item = &'a TrainingData<F>>,
+    iteration: usize,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    learning_rate: impl FnMut(usize) -> f64,
+    optimizer: &mut (impl Optimizer<F> + ?Sized),
+) -> GradientDescentResult {
+    gradient_descent_step(
+        model,
+        training_data,
+        iteration,
+        loss_function,
+        accuracy_function,
+        learning_rate,
+        optimizer,
+        true,
+    )
+    .unwrap()
+}
+
+/// Like `gradient_descent_with_optimizer`, but returns an error instead of panicking, see
+/// `try_gradient_descent`.
+pub fn try_gradient_descent_with_optimizer<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: impl Iterator<Item = &'a TrainingData<F>>,
+    iteration: usize,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    learning_rate: impl FnMut(usize) -> f64,
+    optimizer: &mut (impl Optimizer<F> + ?Sized),
+) -> Result<GradientDescentResult> {
+    gradient_descent_step(
+        model,
+        training_data,
+        iteration,
+        loss_function,
+        accuracy_function,
+        learning_rate,
+        optimizer,
+        false,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gradient_descent_step<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    mut training_data: impl Iterator<Item = &'a TrainingData<F>>,
+    iteration: usize,
+    mut loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    mut accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    mut learning_rate: impl FnMut(usize) -> f64,
+    optimizer: &mut (impl Optimizer<F> + ?Sized),
+    skip_non_finite_loss: bool,
+) -> Result<GradientDescentResult> {
+    struct Acc<F: Float> {
+        total_loss: Value<F>,
         num_accurate: usize,
         batch_size: usize,
     }
@@ -38,9 +144,9 @@ pub fn gradient_descent<'a>(
         total_loss,
         num_accurate,
         batch_size,
-    } = training_data.fold(
+    } = training_data.try_fold(
         Acc {
-            total_loss: Value::new(0.0),
+            total_loss: Value::new(F::ZERO),
             num_accurate: 0,
             batch_size: 0,
         },
@@ -49,7 +155,8 @@ pub fn gradient_descent<'a>(
              input,
              expected_output,
          }| {
-            let output = model.forward(&input.iter().copied().map(Value::new).collect::<Vec<_>>());
+            let output =
+                model.try_forward(&input.iter().copied().map(Value::new).collect::<Vec<_>>())?;
 
             let loss = loss_function(&output, expected_output);
             acc.total_loss = &acc.total_loss + &loss;
@@ -60,52 +167,131 @@ pub fn gradient_descent<'a>(
 
             acc.batch_size += 1;
 
-            acc
+            Ok::<_, Error>(acc)
         },
-    );
+    )?;
+
+    if batch_size == 0 {
+        return Err(Error::EmptyDataset);
+    }
 
-    let mut avg_loss = &total_loss / &Value::new(batch_size as f64);
+    let mut avg_loss = &total_loss / &Value::new(F::from_f64(batch_size as f64));
     let avg_accuracy = num_accurate as f64 / (batch_size as f64);
 
-    avg_loss.backward();
+    if !avg_loss.data().is_finite() {
+        if skip_non_finite_loss {
+            return Ok(GradientDescentResult {
+                avg_loss: avg_loss.data().to_f64(),
+                avg_accuracy,
+                updated: false,
+            });
+        }
+        return Err(Error::NonFiniteLoss(avg_loss.data().to_f64()));
+    }
 
-    let learning_rate = learning_rate(iteration);
+    avg_loss.try_backward()?;
 
-    for mut param in model.parameters() {
-        param.set_data(param.data() - param.grad() * learning_rate);
-    }
+    optimizer.step(
+        &model.parameters().collect::<Vec<_>>(),
+        learning_rate(iteration),
+    );
 
-    GradientDescentResult {
-        avg_loss: avg_loss.data(),
+    Ok(GradientDescentResult {
+        avg_loss: avg_loss.data().to_f64(),
         avg_accuracy,
+        updated: true,
+    })
+}
+
+/// Moves every parameter of `model` against its gradient, scaled by `learning_rate`. Together with
+/// `Value::backward_accumulate` and `Module::zero_grad` this allows accumulating gradients over
+/// several micro-batches before updating.
+pub fn apply_gradients<F: Float>(model: &(impl Module<F> + ?Sized), learning_rate: f64) {
+    for mut param in model.parameters() {
+        param.set_data(param.data() - param.grad() * F::from_f64(learning_rate));
     }
 }
 
-struct RandomSampleIterator<'a> {
-    data: &'a [TrainingData],
+pub struct EvaluationResult {
+    pub avg_loss: f64,
+    pub avg_accuracy: f64,
+}
+
+/// Computes the average loss and accuracy of `model` on `data` without recording the computation
+/// graph.
+///
+/// Panics on the errors reported by `try_evaluate`.
+pub fn evaluate<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    data: impl Iterator<Item = &'a TrainingData<F>>,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+) -> EvaluationResult {
+    try_evaluate(model, data, loss_function, accuracy_function).unwrap()
+}
+
+/// Like `evaluate`, but returns an error if `data` is empty or an input does not match the model.
+pub fn try_evaluate<'a, F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    data: impl Iterator<Item = &'a TrainingData<F>>,
+    mut loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    mut accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+) -> Result<EvaluationResult> {
+    no_grad(|| {
+        let mut total_loss = 0.0;
+        let mut num_accurate = 0;
+        let mut num_examples = 0;
+
+        for TrainingData {
+            input,
+            expected_output,
+        } in data
+        {
+            let output =
+                model.try_forward(&input.iter().copied().map(Value::new).collect::<Vec<_>>())?;
+
+            total_loss += loss_function(&output, expected_output).data().to_f64();
+            if accuracy_function(&output, expected_output) {
+                num_accurate += 1;
+            }
+            num_examples += 1;
+        }
+
+        if num_examples == 0 {
+            return Err(Error::EmptyDataset);
+        }
+
+        Ok(EvaluationResult {
+            avg_loss: total_loss / num_examples as f64,
+            avg_accuracy: num_accurate as f64 / num_examples as f64,
+        })
+    })
+}
+
+/// Yields `batch_size` examples sampled uniformly with replacement from `data`.
+pub struct RandomSampleIterator<'a, F: Float> {
+    data: &'a [TrainingData<F>],
     generated: usize,
     batch_size: usize,
     rng: ThreadRng,
     distribution: Uniform<usize>,
 }
 
-impl<'a> RandomSampleIterator<'a> {
-    fn new(
-        data: &'a [TrainingData],
-        batch_size: usize,
-    ) -> Result<Self, rand::distr::uniform::Error> {
+impl<'a, F: Float> RandomSampleIterator<'a, F> {
+    /// Returns an error if `data` is empty.
+    pub fn new(data: &'a [TrainingData<F>], batch_size: usize) -> Result<Self> {
         Ok(Self {
             data,
             generated: 0,
             batch_size,
             rng: rand::rng(),
-            distribution: Uniform::new(0, data.len())?,
+            distribution: Uniform::new(0, data.len()).map_err(|_| Error::EmptyDataset)?,
         })
     }
 }
 
-impl<'a> Iterator for RandomSampleIterator<'a> {
-    type Item = &'a TrainingData;
+impl<'a, F: Float> Iterator for RandomSampleIterator<'a, F> {
+    type Item = &'a TrainingData<F>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.generated == self.batch_size {
@@ -118,21 +304,148 @@ impl<'a> Iterator for RandomSampleIterator<'a> {
     }
 }
 
-pub fn stochastic_gradient_descent(
-    model: &MultiLayerPerceptron,
-    training_data: &[TrainingData],
+/// Performs a single gradient descent step on `batch_size` examples sampled from `training_data`,
+/// see `gradient_descent`.
+pub fn stochastic_gradient_descent<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: &[TrainingData<F>],
     batch_size: usize,
     iteration: usize,
-    mut loss_function: impl FnMut(&[Value], &[f64]) -> Value,
-    mut accuracy_function: impl FnMut(&[Value], &[f64]) -> bool,
-    mut learning_rate: impl FnMut(usize) -> f64,
+    loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    learning_rate: impl FnMut(usize) -> f64,
 ) -> GradientDescentResult {
     gradient_descent(
         model,
         RandomSampleIterator::new(training_data, batch_size).unwrap(),
         iteration,
+        loss_function,
+        accuracy_function,
+        learning_rate,
+    )
+}
+
+/// Like `stochastic_gradient_descent`, but returns an error instead of panicking, see
+/// `try_gradient_descent`.
+pub fn try_stochastic_gradient_descent<F: Float>(
+    model: &(impl Module<F> + ?Sized),
+    training_data: &[TrainingData<F>],
+    batch_size: usize,
+    iteration: usize,
+    mut loss_function: impl FnMut(&[Value<F>], &[F]) -> Value<F>,
+    mut accuracy_function: impl FnMut(&[Value<F>], &[F]) -> bool,
+    mut learning_rate: impl FnMut(usize) -> f64,
+) -> Result<GradientDescentResult> {
+    try_gradient_descent(
+        model,
+        RandomSampleIterator::new(training_data, batch_size)?,
+        iteration,
         &mut loss_function,
         &mut accuracy_function,
         &mut learning_rate,
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::multi_layer_perceptron::MultiLayerPerceptron;
+
+    fn loss_function(output: &[Value], expected_output: &[f64]) -> Value {
+        output
+            .iter()
+            .zip(expected_output)
+            .fold(Value::new(0.0), |acc, (o, e)| {
+                &acc + &(o - &Value::new(*e)).powf(2.0)
+            })
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let model = MultiLayerPerceptron::new(2, &[3], 1);
+        let data = [
+            TrainingData::new(vec![1.0, 2.0], vec![1.0]),
+            TrainingData::new(vec![-1.0, 0.5], vec![0.0]),
+        ];
+
+        let result = evaluate(&model, data.iter(), loss_function, |_, e| e[0] == 1.0);
+        assert_eq!(result.avg_accuracy, 0.5);
+        assert!(result.avg_loss >= 0.0);
+        assert!(matches!(
+            try_evaluate(&model, [].iter(), loss_function, |_, _| true),
+            Err(Error::EmptyDataset)
+        ));
+    }
+
+    #[test]
+    fn test_f32() {
+        let model = MultiLayerPerceptron::<f32>::new(2, &[3], 1);
+        let data = [TrainingData::new(vec![1.0f32, 2.0], vec![0.5])];
+
+        let loss = |output: &[Value<f32>], expected_output: &[f32]| {
+            (&output[0] - &Value::new(expected_output[0])).powf(2.0)
+        };
+        let before = model.parameters().map(|p| p.data()).collect::<Vec<_>>();
+        let result = gradient_descent(&model, data.iter(), 0, loss, |_, _| true, |_| 0.1);
+        assert!(result.updated);
+        assert!(result.avg_loss.is_finite());
+        assert_ne!(
+            model.parameters().map(|p| p.data()).collect::<Vec<_>>(),
+            before
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let model = MultiLayerPerceptron::new(2, &[3], 1);
+
+        assert!(matches!(
+            try_stochastic_gradient_descent(&model, &[], 1, 0, loss_function, |_, _| true, |_| 0.1),
+            Err(Error::EmptyDataset)
+        ));
+
+        let data = [TrainingData::new(vec![1.0, 2.0, 3.0], vec![1.0])];
+        assert!(matches!(
+            try_stochastic_gradient_descent(
+                &model,
+                &data,
+                1,
+                0,
+                loss_function,
+                |_, _| true,
+                |_| 0.1
+            ),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 3
+            })
+        ));
+
+        let data = [TrainingData::new(vec![1.0, 2.0], vec![f64::NAN])];
+        let before = model.parameters().map(|p| p.data()).collect::<Vec<_>>();
+        assert!(matches!(
+            try_stochastic_gradient_descent(
+                &model,
+                &data,
+                1,
+                0,
+                loss_function,
+                |_, _| true,
+                |_| 0.1
+            ),
+            Err(Error::NonFiniteLoss(_))
+        ));
+        assert_eq!(
+            model.parameters().map(|p| p.data()).collect::<Vec<_>>(),
+            before
+        );
+
+        let result =
+            stochastic_gradient_descent(&model, &data, 1, 0, loss_function, |_, _| true, |_| 0.1);
+        assert!(!result.updated);
+        assert_eq!(
+            model.parameters().map(|p| p.data()).collect::<Vec<_>>(),
+            before
+        );
+    }
+}
@@ -1,6 +1,13 @@
 mod backprop;
 mod base;
+mod custom;
+mod dot;
+mod gradients;
+mod mode;
 mod ops;
 
 use base::Op;
 pub use base::Value;
+pub use custom::CustomOp;
+pub use dot::DotOptions;
+pub use mode::{detect_anomaly, is_grad_enabled, no_grad};
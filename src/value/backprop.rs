@@ -1,18 +1,27 @@
-use super::Op;
-use std::collections::{HashMap, hash_map::Entry};
+use super::{Op, mode::is_anomaly_detection_enabled};
+use crate::error::{Error, Result};
+use std::{cell::Cell, collections::HashMap};
 
 use super::Value;
+use crate::float::Float;
 
-impl Value {
-    fn children(&self) -> impl Iterator<Item = Value> {
-        struct ValueChildrenIterator {
-            children: [Option<Value>; 2],
+thread_local! {
+    /// Generation of the last graph traversal on this thread, used to mark visited nodes.
+    static GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+impl<F: Float> Value<F> {
+    pub(super) fn children(&self) -> impl Iterator<Item = Value<F>> {
+        struct ValueChildrenIterator<F: Float> {
+            children: [Option<Value<F>>; 2],
             index: usize,
+            custom_inputs: std::vec::IntoIter<Value<F>>,
         }
 
-        impl ValueChildrenIterator {
-            fn new(op: Option<Op>) -> Self {
+        impl<F: Float> ValueChildrenIterator<F> {
+            fn new(op: Option<Op<F>>) -> Self {
                 let mut children = [None, None];
+                let mut custom_inputs = Vec::new();
 
                 match op {
                     Some(Op::Add(x, y)) => {
@@ -29,100 +38,281 @@ impl Value {
                     Some(Op::Tanh(x)) => {
                         children[0] = Some(x);
                     }
+                    Some(Op::Relu(x)) => {
+                        children[0] = Some(x);
+                    }
+                    Some(Op::Custom { op: _, inputs }) => {
+                        custom_inputs = inputs;
+                    }
                     None => {}
                 }
 
-                Self { children, index: 0 }
+                Self {
+                    children,
+                    index: 0,
+                    custom_inputs: custom_inputs.into_iter(),
+                }
             }
         }
 
-        impl Iterator for ValueChildrenIterator {
-            type Item = Value;
+        impl<F: Float> Iterator for ValueChildrenIterator<F> {
+            type Item = Value<F>;
 
             fn next(&mut self) -> Option<Self::Item> {
                 let child = self.children.get_mut(self.index).and_then(|x| x.take());
                 self.index += 1;
-                child
+                child.or_else(|| self.custom_inputs.next())
             }
         }
 
         ValueChildrenIterator::new(self.prev())
     }
 
-    fn topological_sort(&self) -> Vec<Value> {
-        #[allow(clippy::mutable_key_type)]
-        let mut in_degree: HashMap<Value, usize> = HashMap::new();
-        let mut stack = Vec::new();
+    /// Sorts all nodes reachable from this value such that every node comes before its children,
+    /// using an iterative depth-first search. Visited nodes are marked with a per-traversal
+    /// generation instead of being tracked in a separate set.
+    pub(super) fn topological_sort(&self) -> Result<Vec<Value<F>>> {
+        Self::topological_sort_all(std::slice::from_ref(self))
+    }
 
-        // First pass to initialize in_degree
-        in_degree.insert(self.clone(), 0);
-        stack.push(self.clone());
-        while let Some(val) = stack.pop() {
-            for child in val.children() {
-                match in_degree.entry(child.clone()) {
-                    Entry::Occupied(mut occupied_entry) => *(occupied_entry.get_mut()) += 1,
-                    Entry::Vacant(vacant_entry) => {
-                        vacant_entry.insert(1);
-                        stack.push(child);
-                    }
+    /// Like `topological_sort`, but for all nodes reachable from any of `roots`.
+    fn topological_sort_all(roots: &[Value<F>]) -> Result<Vec<Value<F>>> {
+        let generation = GENERATION.get() + 1;
+        GENERATION.set(generation);
+        let (in_progress, done) = (2 * generation, 2 * generation + 1);
+
+        let mut post_order = Vec::new();
+        let mut stack = roots
+            .iter()
+            .rev()
+            .map(|root| (root.clone(), false))
+            .collect::<Vec<_>>();
+        while let Some((val, expanded)) = stack.pop() {
+            if expanded {
+                val.set_mark(done);
+                post_order.push(val);
+                continue;
+            }
+
+            let mark = val.mark();
+            if mark == done {
+                continue;
+            }
+            // Only nodes on the current path are in progress, so we found a loop
+            if mark == in_progress {
+                return Err(Error::CyclicGraph);
+            }
+
+            // Revisit the node once all of its children are done
+            val.set_mark(in_progress);
+            stack.push((val.clone(), true));
+            let mut has_loop = false;
+            val.with_prev(|op| {
+                if let Some(op) = op {
+                    op.for_each_child(|child| match child.mark() {
+                        mark if mark == done => {}
+                        mark if mark == in_progress => has_loop = true,
+                        _ => stack.push((child.clone(), false)),
+                    });
                 }
+            });
+            if has_loop {
+                return Err(Error::CyclicGraph);
             }
         }
 
-        // Should be 0, otherwise there is a loop
-        assert_eq!(in_degree.get(self), Some(&0));
+        post_order.reverse();
+        Ok(post_order)
+    }
 
-        // Second pass to determine sorting
-        let mut sorting = Vec::new();
-        stack.push(self.clone());
-        while let Some(val) = stack.pop() {
-            for child in val.children() {
-                let ind = in_degree.get_mut(&child).unwrap();
-                *ind -= 1;
-                if *ind == 0 {
-                    stack.push(child);
-                }
+    pub fn backward(&mut self) {
+        self.try_backward().unwrap();
+    }
+
+    /// Like `backward`, but returns an error instead of panicking if the graph contains a cycle,
+    /// anomaly detection finds a non-finite value or a `CustomOp` doesn't return one gradient per
+    /// input.
+    pub fn try_backward(&mut self) -> Result<()> {
+        Self::backpropagate(std::slice::from_ref(self), &[F::ONE], false)
+    }
+
+    /// Like `backward`, but adds to the gradients of leaves instead of overwriting them, so the
+    /// gradients of several graphs (e.g. micro-batches) can be accumulated before an update.
+    pub fn backward_accumulate(&mut self) {
+        self.try_backward_accumulate().unwrap();
+    }
+
+    pub fn try_backward_accumulate(&mut self) -> Result<()> {
+        Self::backpropagate(std::slice::from_ref(self), &[F::ONE], true)
+    }
+
+    /// Backpropagates from several `outputs` at once, seeding each with the corresponding entry
+    /// of `seed_grads` instead of 1. Afterwards the `grad` of every node holds the
+    /// vector-Jacobian product `seed_grads^T * J`.
+    pub fn backward_with(outputs: &[Value<F>], seed_grads: &[F]) {
+        Self::try_backward_with(outputs, seed_grads).unwrap();
+    }
+
+    /// Like `backward_with`, but returns an error instead of panicking, including when
+    /// `seed_grads` doesn't have one entry per output.
+    pub fn try_backward_with(outputs: &[Value<F>], seed_grads: &[F]) -> Result<()> {
+        if seed_grads.len() != outputs.len() {
+            return Err(Error::DimensionMismatch {
+                expected: outputs.len(),
+                actual: seed_grads.len(),
+            });
+        }
+        Self::backpropagate(outputs, seed_grads, false)
+    }
+
+    /// Computes the Jacobian of `outputs` with respect to `inputs`, with one row per output. The
+    /// graph is sorted once and then backpropagated once per output, so the `grad` of the nodes
+    /// holds the gradients of the last output afterwards.
+    pub fn jacobian(outputs: &[Value<F>], inputs: &[Value<F>]) -> Vec<Vec<F>> {
+        Self::try_jacobian(outputs, inputs).unwrap()
+    }
+
+    pub fn try_jacobian(outputs: &[Value<F>], inputs: &[Value<F>]) -> Result<Vec<Vec<F>>> {
+        let topological_sorting = Self::topological_sort_all(outputs)?;
+        #[allow(clippy::mutable_key_type)]
+        let depths = Self::anomaly_depths(&topological_sorting)?;
+
+        let mut seed_grads = vec![F::ZERO; outputs.len()];
+        let mut jacobian = Vec::with_capacity(outputs.len());
+        for i in 0..outputs.len() {
+            // Inputs that don't reach any output are not part of the sorting, so their grads
+            // would otherwise keep whatever value they had before
+            for input in inputs {
+                input.set_grad(F::ZERO);
             }
-            sorting.push(val);
+            seed_grads[i] = F::ONE;
+            Self::propagate(&topological_sorting, outputs, &seed_grads, false, &depths)?;
+            seed_grads[i] = F::ZERO;
+            jacobian.push(inputs.iter().map(Value::grad).collect());
         }
 
-        // Assert that everything has been processed, otherwise there is a loop
-        assert_eq!(sorting.len(), in_degree.len());
+        Ok(jacobian)
+    }
 
-        sorting
+    fn backpropagate(roots: &[Value<F>], seed_grads: &[F], accumulate: bool) -> Result<()> {
+        let topological_sorting = Self::topological_sort_all(roots)?;
+        #[allow(clippy::mutable_key_type)]
+        let depths = Self::anomaly_depths(&topological_sorting)?;
+        Self::propagate(&topological_sorting, roots, seed_grads, accumulate, &depths)
     }
 
-    pub fn backward(&mut self) {
-        let mut topological_sorting = self.topological_sort();
+    /// With anomaly detection enabled, checks the forward pass before backpropagating and returns
+    /// the node depths for reporting. Otherwise returns an empty map.
+    #[allow(clippy::mutable_key_type)]
+    fn anomaly_depths(topological_sorting: &[Value<F>]) -> Result<HashMap<Value<F>, usize>> {
+        if is_anomaly_detection_enabled() {
+            Self::check_forward_anomalies(topological_sorting)
+        } else {
+            Ok(HashMap::new())
+        }
+    }
+
+    #[allow(clippy::mutable_key_type)]
+    fn propagate(
+        topological_sorting: &[Value<F>],
+        roots: &[Value<F>],
+        seed_grads: &[F],
+        accumulate: bool,
+        depths: &HashMap<Value<F>, usize>,
+    ) -> Result<()> {
+        let detect_anomaly = is_anomaly_detection_enabled();
 
-        // Set all gradients to 0
-        for val in topological_sorting.iter_mut() {
-            val.set_grad(0.0);
+        // Set all gradients to 0, keeping the gradients of leaves when accumulating
+        for val in topological_sorting.iter() {
+            if !accumulate || !val.is_leaf() {
+                val.set_grad(F::ZERO);
+            }
         }
 
-        // Set root gradient to 1.0
-        self.set_grad(1.0);
+        // Seed the root gradients
+        for (root, &seed_grad) in roots.iter().zip(seed_grads) {
+            root.add_grad(seed_grad);
+        }
 
         // Backpropagate
-        for val in topological_sorting.into_iter() {
-            match val.prev() {
-                Some(Op::Add(mut x, mut y)) => {
-                    x.set_grad(x.grad() + val.grad());
-                    y.set_grad(y.grad() + val.grad());
-                }
-                Some(Op::Mul(mut x, mut y)) => {
-                    x.set_grad(x.grad() + val.grad() * y.data());
-                    y.set_grad(y.grad() + val.grad() * x.data());
-                }
-                Some(Op::Pow { mut base, exp }) => {
-                    base.set_grad(base.grad() + val.grad() * exp * base.data().powf(exp - 1.0));
-                }
-                Some(Op::Tanh(mut x)) => {
-                    x.set_grad(x.grad() + val.grad() * (1.0 - x.data().tanh().powi(2)));
+        for val in topological_sorting.iter() {
+            let grad = val.grad();
+
+            let op_name = val.with_prev(|op| {
+                match op {
+                    Some(Op::Add(x, y)) => {
+                        x.add_grad(grad);
+                        y.add_grad(grad);
+                    }
+                    Some(Op::Mul(x, y)) => {
+                        let (x_data, y_data) = (x.data(), y.data());
+                        x.add_grad(grad * y_data);
+                        y.add_grad(grad * x_data);
+                    }
+                    Some(Op::Pow { base, exp }) => {
+                        base.add_grad(grad * *exp * base.data().powf(*exp - F::ONE));
+                    }
+                    Some(Op::Tanh(x)) => {
+                        let tanh = val.data();
+                        x.add_grad(grad * (F::ONE - tanh * tanh));
+                    }
+                    Some(Op::Relu(x)) => {
+                        let slope = if x.data() > F::ZERO { F::ONE } else { F::ZERO };
+                        x.add_grad(grad * slope);
+                    }
+                    Some(Op::Custom { op, inputs }) => {
+                        let input_grads = val.custom_op_input_grads(op.as_ref(), inputs, grad)?;
+                        for (input, grad) in inputs.iter().zip(input_grads) {
+                            input.add_grad(grad);
+                        }
+                    }
+                    None => {}
                 }
-                None => {}
+                Ok::<_, Error>(op.map_or("Leaf", Op::name))
+            })?;
+
+            if detect_anomaly
+                && let Some(child) = val.children().find(|child| !child.grad().is_finite())
+            {
+                return Err(Error::Anomaly {
+                    op: op_name,
+                    depth: depths[val],
+                    value: child.grad().to_f64(),
+                    in_backward: true,
+                });
             }
         }
+
+        Ok(())
+    }
+
+    /// Computes the graph depth of every node and returns an error for the first node whose data
+    /// is non-finite although all of its children are finite.
+    #[allow(clippy::mutable_key_type)]
+    fn check_forward_anomalies(
+        topological_sorting: &[Value<F>],
+    ) -> Result<HashMap<Value<F>, usize>> {
+        let mut depths = HashMap::new();
+        for val in topological_sorting.iter() {
+            let depth = *depths.entry(val.clone()).or_insert(0);
+            for child in val.children() {
+                let child_depth = depths.entry(child).or_insert(0);
+                *child_depth = usize::max(*child_depth, depth + 1);
+            }
+        }
+
+        for val in topological_sorting.iter().rev() {
+            if !val.data().is_finite() && val.children().all(|child| child.data().is_finite()) {
+                return Err(Error::Anomaly {
+                    op: val.prev().as_ref().map_or("Leaf", Op::name),
+                    depth: depths[val],
+                    value: val.data().to_f64(),
+                    in_backward: false,
+                });
+            }
+        }
+
+        Ok(depths)
     }
 }
 
@@ -175,4 +365,126 @@ mod tests {
         assert_eq!(b.grad(), 0.6411521158456308);
         assert_eq!(c.grad(), 1.0);
     }
+
+    #[test]
+    fn test5() {
+        let a = Value::new(2.0);
+        let b = Value::new(-3.0);
+        let mut c = &(&a * &b).relu() + &(&a + &b).sigmoid();
+        c.backward();
+        let s = 1.0 / (1.0 + 1.0f64.exp());
+        assert!((a.grad() - s * (1.0 - s)).abs() < 1e-12);
+        assert!((b.grad() - s * (1.0 - s)).abs() < 1e-12);
+        assert_eq!(c.grad(), 1.0);
+    }
+
+    #[test]
+    fn test_jacobian() {
+        let a = Value::new(3.0);
+        let b = Value::new(7.0);
+        let outputs = [&a * &b, &a + &(&b * &b)];
+        let inputs = [a.clone(), b.clone()];
+
+        let jacobian = Value::jacobian(&outputs, &inputs);
+        assert_eq!(jacobian, vec![vec![7.0, 3.0], vec![1.0, 14.0]]);
+
+        // The vector-Jacobian product matches the weighted sum of the rows
+        Value::backward_with(&outputs, &[2.0, 3.0]);
+        assert_eq!(a.grad(), 2.0 * 7.0 + 3.0 * 1.0);
+        assert_eq!(b.grad(), 2.0 * 3.0 + 3.0 * 14.0);
+
+        assert!(matches!(
+            Value::try_backward_with(&outputs, &[1.0]),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_jacobian_unreachable_inputs() {
+        let a = Value::new(3.0);
+        let b = Value::new(7.0);
+        let c = Value::new(5.0);
+        // Leave stale grads from an unrelated backward pass
+        (&(&a * &b) * &c).backward();
+
+        // `b` only affects the second output and `c` none of them
+        let outputs = [&a * &a, &a + &b];
+        let inputs = [a.clone(), b.clone(), c.clone()];
+
+        let jacobian = Value::jacobian(&outputs, &inputs);
+        assert_eq!(jacobian, vec![vec![6.0, 0.0, 0.0], vec![1.0, 1.0, 0.0]]);
+    }
+
+    #[test]
+    fn test_deep_graph() {
+        // Long chains must not overflow the stack, neither in backward nor when dropped
+        let a = Value::new(1.0);
+        let mut b = a.clone();
+        for _ in 0..100_000 {
+            b = &b + &a;
+        }
+        b.backward();
+        assert_eq!(a.grad(), 100_001.0);
+    }
+
+    #[test]
+    fn test_anomaly() {
+        use crate::value::detect_anomaly;
+
+        let a: Value = Value::new(0.0);
+        let b = Value::new(2.0);
+        let mut c = &a.powf(0.5) * &b;
+
+        // Without anomaly detection the infinite gradient is silently propagated
+        c.backward();
+        assert!(a.grad().is_infinite());
+
+        let result = detect_anomaly(|| c.try_backward());
+        assert!(matches!(
+            result,
+            Err(Error::Anomaly {
+                op: "Pow",
+                depth: 1,
+                in_backward: true,
+                ..
+            })
+        ));
+
+        let mut d = &Value::new(-1.0).powf(0.5) + &b;
+        let result = detect_anomaly(|| d.try_backward());
+        assert!(matches!(
+            result,
+            Err(Error::Anomaly {
+                op: "Pow",
+                depth: 1,
+                in_backward: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_accumulate() {
+        let a = Value::new(3.0);
+        let b = Value::new(7.0);
+
+        let mut c = &a * &b;
+        c.backward();
+        let mut d = &(&a * &a) + &b.detach();
+        d.backward_accumulate();
+        assert_eq!(a.grad(), 7.0 + 6.0);
+        assert_eq!(b.grad(), 3.0);
+
+        // Calling backward again on the same graph accumulates again
+        d.backward_accumulate();
+        assert_eq!(a.grad(), 7.0 + 6.0 + 6.0);
+        assert_eq!(d.grad(), 1.0);
+
+        let mut a = a;
+        a.zero_grad();
+        assert_eq!(a.grad(), 0.0);
+    }
 }
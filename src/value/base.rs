@@ -1,74 +1,182 @@
 use std::{cell::RefCell, rc::Rc};
 
-pub struct Value(Rc<RefCell<InnerValue>>);
+use super::{CustomOp, mode::is_grad_enabled};
+use crate::float::Float;
 
-struct InnerValue {
-    data: f64,
-    grad: f64,
-    prev: Option<Op>,
+pub struct Value<F: Float = f64>(Rc<RefCell<InnerValue<F>>>);
+
+struct InnerValue<F: Float> {
+    data: F,
+    grad: F,
+    prev: Option<Op<F>>,
+    /// Scratch space for graph traversals, see `Value::topological_sort`.
+    mark: u64,
+}
+
+impl<F: Float> Drop for InnerValue<F> {
+    /// Unlinks the graph iteratively, so dropping a long chain of values doesn't overflow the
+    /// stack.
+    fn drop(&mut self) {
+        let Some(mut op) = self.prev.take() else {
+            return;
+        };
+        let mut stack = Vec::new();
+        loop {
+            op.for_each_child(|child| {
+                if Rc::strong_count(&child.0) == 1
+                    && let Some(prev) = child.0.borrow_mut().prev.take()
+                {
+                    stack.push(prev);
+                }
+            });
+            drop(op);
+            match stack.pop() {
+                Some(next) => op = next,
+                None => break,
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
-pub(super) enum Op {
-    Add(Value, Value),
-    Mul(Value, Value),
-    Pow { base: Value, exp: f64 },
-    Tanh(Value),
+pub(super) enum Op<F: Float> {
+    Add(Value<F>, Value<F>),
+    Mul(Value<F>, Value<F>),
+    Pow {
+        base: Value<F>,
+        exp: F,
+    },
+    Tanh(Value<F>),
+    Relu(Value<F>),
+    Custom {
+        op: Rc<dyn CustomOp<F>>,
+        inputs: Vec<Value<F>>,
+    },
+}
+
+impl<F: Float> Op<F> {
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Op::Add(..) => "Add",
+            Op::Mul(..) => "Mul",
+            Op::Pow { .. } => "Pow",
+            Op::Tanh(..) => "Tanh",
+            Op::Relu(..) => "Relu",
+            Op::Custom { op, .. } => op.name(),
+        }
+    }
 }
 
-impl Value {
-    pub fn new(data: f64) -> Self {
+impl<F: Float> Op<F> {
+    pub(super) fn for_each_child(&self, mut f: impl FnMut(&Value<F>)) {
+        match self {
+            Op::Add(x, y) | Op::Mul(x, y) => {
+                f(x);
+                f(y);
+            }
+            Op::Pow { base, exp: _ } => f(base),
+            Op::Tanh(x) | Op::Relu(x) => f(x),
+            Op::Custom { op: _, inputs } => inputs.iter().for_each(f),
+        }
+    }
+}
+
+impl<F: Float> Value<F> {
+    pub fn new(data: F) -> Self {
         Value(Rc::new(RefCell::new(InnerValue {
             data,
-            grad: 0.0,
+            grad: F::ZERO,
             prev: None,
+            mark: 0,
         })))
     }
 
-    pub(super) fn with_op(data: f64, op: Op) -> Value {
+    /// Creates a value produced by `op`, or a leaf if gradients are disabled by `no_grad`.
+    pub(super) fn with_op(data: F, op: Op<F>) -> Self {
         Value(Rc::new(RefCell::new(InnerValue {
             data,
-            grad: 0.0,
-            prev: Some(op),
+            grad: F::ZERO,
+            prev: is_grad_enabled().then_some(op),
+            mark: 0,
         })))
     }
 
-    pub fn data(&self) -> f64 {
+    pub fn data(&self) -> F {
         self.0.borrow().data
     }
 
-    pub fn set_data(&mut self, val: f64) {
+    pub fn set_data(&mut self, val: F) {
         self.0.borrow_mut().data = val;
     }
 
-    pub fn grad(&self) -> f64 {
+    pub fn grad(&self) -> F {
         self.0.borrow().grad
     }
 
-    pub(super) fn set_grad(&mut self, val: f64) {
+    pub fn zero_grad(&mut self) {
+        self.set_grad(F::ZERO);
+    }
+
+    /// Returns a new leaf with the same data, so gradients do not flow back into this value.
+    pub fn detach(&self) -> Self {
+        Value::new(self.data())
+    }
+
+    pub(super) fn set_grad(&self, val: F) {
         self.0.borrow_mut().grad = val;
     }
 
-    pub(super) fn prev(&self) -> Option<Op> {
+    pub(super) fn add_grad(&self, delta: F) {
+        let mut inner = self.0.borrow_mut();
+        inner.grad = inner.grad + delta;
+    }
+
+    pub(super) fn prev(&self) -> Option<Op<F>> {
         self.0.borrow().prev.clone()
     }
+
+    /// Calls `f` with the operation that produced this value without cloning it.
+    pub(super) fn with_prev<R>(&self, f: impl FnOnce(Option<&Op<F>>) -> R) -> R {
+        f(self.0.borrow().prev.as_ref())
+    }
+
+    pub(super) fn is_leaf(&self) -> bool {
+        self.0.borrow().prev.is_none()
+    }
+
+    pub(super) fn mark(&self) -> u64 {
+        self.0.borrow().mark
+    }
+
+    pub(super) fn set_mark(&self, mark: u64) {
+        self.0.borrow_mut().mark = mark;
+    }
+}
+
+impl<F: Float> std::fmt::Debug for Value<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Value")
+            .field("data", &self.data())
+            .field("grad", &self.grad())
+            .finish()
+    }
 }
 
-impl Clone for Value {
+impl<F: Float> Clone for Value<F> {
     fn clone(&self) -> Self {
         Value(Rc::clone(&self.0))
     }
 }
 
-impl PartialEq for Value {
+impl<F: Float> PartialEq for Value<F> {
     fn eq(&self, other: &Self) -> bool {
         Rc::ptr_eq(&self.0, &other.0)
     }
 }
 
-impl Eq for Value {}
+impl<F: Float> Eq for Value<F> {}
 
-impl std::hash::Hash for Value {
+impl<F: Float> std::hash::Hash for Value<F> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         state.write_usize(Rc::as_ptr(&self.0) as usize);
     }
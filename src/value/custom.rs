@@ -0,0 +1,151 @@
+use std::rc::Rc;
+
+use super::{Op, Value};
+use crate::{
+    error::{Error, Result},
+    float::Float,
+};
+
+/// A differentiable operation defined outside of this crate, applied with `Value::custom_op`.
+pub trait CustomOp<F: Float = f64> {
+    fn name(&self) -> &'static str;
+
+    /// Computes the output from the data of the inputs.
+    fn forward(&self, inputs: &[F]) -> F;
+
+    /// Given the data of the inputs, the output and the gradient of the output, returns the
+    /// gradient of every input.
+    fn backward(&self, inputs: &[F], output: F, grad: F) -> Vec<F>;
+}
+
+impl<F: Float> Value<F> {
+    /// Applies `op` to `inputs`, recording it in the computation graph like the built-in
+    /// operations. Gradients of custom operations are treated as constants by `gradients`, so
+    /// they only support first order derivatives.
+    pub fn custom_op(op: impl CustomOp<F> + 'static, inputs: &[Value<F>]) -> Self {
+        Self::custom_op_rc(Rc::new(op), inputs)
+    }
+
+    /// Like `custom_op`, but allows sharing one instance of `op` between nodes.
+    pub fn custom_op_rc(op: Rc<dyn CustomOp<F>>, inputs: &[Value<F>]) -> Self {
+        let data = op.forward(&inputs.iter().map(Value::data).collect::<Vec<_>>());
+        Value::with_op(
+            data,
+            Op::Custom {
+                op,
+                inputs: Vec::from(inputs),
+            },
+        )
+    }
+
+    /// Gradients of the inputs of a custom operation producing this value, given its gradient.
+    /// Returns an error if `op` doesn't return one gradient per input.
+    pub(super) fn custom_op_input_grads(
+        &self,
+        op: &dyn CustomOp<F>,
+        inputs: &[Value<F>],
+        grad: F,
+    ) -> Result<Vec<F>> {
+        let input_grads = op.backward(
+            &inputs.iter().map(Value::data).collect::<Vec<_>>(),
+            self.data(),
+            grad,
+        );
+        if input_grads.len() != inputs.len() {
+            return Err(Error::DimensionMismatch {
+                expected: inputs.len(),
+                actual: input_grads.len(),
+            });
+        }
+        Ok(input_grads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Huber {
+        delta: f64,
+    }
+
+    impl CustomOp for Huber {
+        fn name(&self) -> &'static str {
+            "Huber"
+        }
+
+        fn forward(&self, inputs: &[f64]) -> f64 {
+            let diff = inputs[0] - inputs[1];
+            if diff.abs() <= self.delta {
+                0.5 * diff * diff
+            } else {
+                self.delta * (diff.abs() - 0.5 * self.delta)
+            }
+        }
+
+        fn backward(&self, inputs: &[f64], _output: f64, grad: f64) -> Vec<f64> {
+            let diff = inputs[0] - inputs[1];
+            let d = diff.clamp(-self.delta, self.delta);
+            vec![grad * d, -grad * d]
+        }
+    }
+
+    #[test]
+    fn test() {
+        let op: Rc<dyn CustomOp> = Rc::new(Huber { delta: 1.0 });
+        let a = Value::new(3.0);
+        let b = Value::new(0.5);
+        let c = Value::new(0.25);
+
+        let mut loss = &Value::custom_op_rc(op.clone(), &[a.clone(), b.clone()])
+            + &(&Value::custom_op_rc(op, &[c.clone(), b.clone()]) * &Value::new(2.0));
+        assert_eq!(loss.data(), 2.0 + 2.0 * 0.5 * 0.0625);
+
+        loss.backward();
+        assert_eq!(a.grad(), 1.0);
+        assert_eq!(b.grad(), -1.0 + 2.0 * 0.25);
+        assert_eq!(c.grad(), 2.0 * -0.25);
+
+        let grads = loss.gradients(&[a.clone(), c.clone()]);
+        assert_eq!(grads[0].data(), 1.0);
+        assert_eq!(grads[1].data(), -0.5);
+    }
+
+    struct WrongArity;
+
+    impl CustomOp for WrongArity {
+        fn name(&self) -> &'static str {
+            "WrongArity"
+        }
+
+        fn forward(&self, inputs: &[f64]) -> f64 {
+            inputs.iter().sum()
+        }
+
+        fn backward(&self, _inputs: &[f64], _output: f64, grad: f64) -> Vec<f64> {
+            vec![grad]
+        }
+    }
+
+    #[test]
+    fn test_wrong_number_of_grads() {
+        let a = Value::new(1.0);
+        let b = Value::new(2.0);
+        let mut c = Value::custom_op(WrongArity, &[a.clone(), b.clone()]);
+
+        assert!(matches!(
+            c.try_backward(),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+        assert!(matches!(
+            c.try_gradients(&[a, b]),
+            Err(Error::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use super::{Op, Value};
+use crate::float::Float;
+
+/// Options for `Value::to_dot_with`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DotOptions {
+    /// Render every neuron, i.e. a `Tanh`/`Relu` node together with the chain of `Add`/`Mul`
+    /// nodes computing its pre-activation, as a single node. Leaf inputs of a neuron (weights,
+    /// bias and model inputs) are only counted instead of drawn.
+    pub collapse_neurons: bool,
+}
+
+impl<F: Float> Value<F> {
+    /// Renders the computation graph ending in this value in the Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(DotOptions::default())
+    }
+
+    pub fn to_dot_with(&self, options: DotOptions) -> String {
+        let sorting = self.topological_sort().unwrap();
+
+        #[allow(clippy::mutable_key_type)]
+        let ids = sorting
+            .iter()
+            .enumerate()
+            .map(|(i, val)| (val.clone(), i))
+            .collect::<HashMap<_, _>>();
+
+        // Maps nodes hidden inside a collapsed neuron to the id of the neuron
+        let mut collapsed_into = HashMap::new();
+        // Inputs of every collapsed neuron that are drawn, and the number of hidden leaf inputs
+        let mut neuron_inputs = HashMap::new();
+
+        if options.collapse_neurons {
+            for val in sorting.iter() {
+                if collapsed_into.contains_key(&ids[val]) || !is_neuron(val) {
+                    continue;
+                }
+
+                let mut inputs = Vec::new();
+                let mut num_leaves = 0;
+                let mut stack = val.children().collect::<Vec<_>>();
+                while let Some(node) = stack.pop() {
+                    match node.prev() {
+                        Some(Op::Add(..) | Op::Mul(..)) => {
+                            if collapsed_into.insert(ids[&node], ids[val]).is_none() {
+                                stack.extend(node.children());
+                            }
+                        }
+                        Some(_) => inputs.push(ids[&node]),
+                        None => num_leaves += 1,
+                    }
+                }
+
+                neuron_inputs.insert(ids[val], (inputs, num_leaves));
+            }
+        }
+
+        let mut dot = String::from("digraph {\n    rankdir=LR;\n    node [shape=record];\n");
+        let mut edges = HashSet::new();
+        // Parents come before their children in the sorting, so a node is only drawn once an
+        // already drawn node has an edge from it
+        let mut visible = HashSet::from([0]);
+
+        for (id, val) in sorting.iter().enumerate() {
+            if !visible.contains(&id) {
+                continue;
+            }
+
+            let op = val.prev();
+            let label = match (&op, neuron_inputs.get(&id)) {
+                (Some(op), Some((_, num_leaves))) => {
+                    format!("{} neuron | {num_leaves} leaf inputs", op.name())
+                }
+                (Some(Op::Pow { exp, .. }), None) => format!("Pow {exp}"),
+                (Some(op), None) => op.name().to_string(),
+                (None, _) => "Leaf".to_string(),
+            };
+            writeln!(
+                dot,
+                "    n{id} [label=\"{{ {label} | data {:.4} | grad {:.4} }}\"];",
+                val.data().to_f64(),
+                val.grad().to_f64()
+            )
+            .unwrap();
+
+            let inputs = match neuron_inputs.get(&id) {
+                Some((inputs, _)) => inputs.clone(),
+                None => val.children().map(|child| ids[&child]).collect(),
+            };
+            for input in inputs {
+                let input = collapsed_into.get(&input).copied().unwrap_or(input);
+                visible.insert(input);
+                if edges.insert((input, id)) {
+                    writeln!(dot, "    n{input} -> n{id};").unwrap();
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn is_neuron<F: Float>(val: &Value<F>) -> bool {
+    match val.prev() {
+        Some(Op::Tanh(x) | Op::Relu(x)) => matches!(x.prev(), Some(Op::Add(..) | Op::Mul(..))),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{module::Module, multi_layer_perceptron::MultiLayerPerceptron};
+
+    #[test]
+    fn test() {
+        let a = Value::new(3.0);
+        let b = Value::new(7.0);
+        let mut c = (&a * &b).tanh();
+        c.backward();
+
+        let dot = c.to_dot();
+        assert!(dot.starts_with("digraph {"));
+        assert_eq!(dot.matches("[label=").count(), 4);
+        assert_eq!(dot.matches(" -> ").count(), 3);
+        assert!(dot.contains("Tanh"));
+    }
+
+    #[test]
+    fn test_collapse_neurons() {
+        let mlp = MultiLayerPerceptron::new(3, &[4], 2);
+        let output = mlp.forward(&[1.0, 2.0, 3.0].map(Value::new));
+        let sum = &output[0] + &output[1];
+
+        let dot = sum.to_dot_with(DotOptions {
+            collapse_neurons: true,
+        });
+        // 6 neurons and the final addition
+        assert_eq!(dot.matches("[label=").count(), 7);
+        assert_eq!(dot.matches("neuron").count(), 6);
+        // Every hidden neuron feeds both output neurons, which feed the addition
+        assert_eq!(dot.matches(" -> ").count(), 10);
+    }
+}
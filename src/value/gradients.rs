@@ -0,0 +1,137 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use super::{Op, Value};
+use crate::{error::Result, float::Float};
+
+impl<F: Float> Value<F> {
+    /// Computes the gradients of this value with respect to `inputs` as new `Value` graphs instead
+    /// of plain numbers, so they can be differentiated again (second derivatives, Hessian-vector
+    /// products, gradient penalties). Unlike `backward`, the `grad` of the nodes is left untouched.
+    pub fn gradients(&self, inputs: &[Value<F>]) -> Vec<Value<F>> {
+        self.try_gradients(inputs).unwrap()
+    }
+
+    /// Like `gradients`, but returns an error instead of panicking if the graph contains a cycle or
+    /// a `CustomOp` doesn't return one gradient per input.
+    pub fn try_gradients(&self, inputs: &[Value<F>]) -> Result<Vec<Value<F>>> {
+        #[allow(clippy::mutable_key_type)]
+        let mut grads: HashMap<Value<F>, Value<F>> = HashMap::new();
+        grads.insert(self.clone(), Value::new(F::ONE));
+
+        #[allow(clippy::mutable_key_type)]
+        fn accumulate<F: Float>(
+            grads: &mut HashMap<Value<F>, Value<F>>,
+            val: Value<F>,
+            grad: Value<F>,
+        ) {
+            match grads.entry(val) {
+                Entry::Occupied(mut occupied_entry) => {
+                    let sum = occupied_entry.get() + &grad;
+                    occupied_entry.insert(sum);
+                }
+                Entry::Vacant(vacant_entry) => {
+                    vacant_entry.insert(grad);
+                }
+            }
+        }
+
+        for val in self.topological_sort()? {
+            let Some(grad) = grads.get(&val).cloned() else {
+                continue;
+            };
+
+            match val.prev() {
+                Some(Op::Add(x, y)) => {
+                    accumulate(&mut grads, x, grad.clone());
+                    accumulate(&mut grads, y, grad);
+                }
+                Some(Op::Mul(x, y)) => {
+                    accumulate(&mut grads, x.clone(), &grad * &y);
+                    accumulate(&mut grads, y, &grad * &x);
+                }
+                Some(Op::Pow { base, exp }) => {
+                    let local = &Value::new(exp) * &base.powf(exp - F::ONE);
+                    accumulate(&mut grads, base, &grad * &local);
+                }
+                Some(Op::Tanh(x)) => {
+                    let local = &Value::new(F::ONE) - &(&val * &val);
+                    accumulate(&mut grads, x, &grad * &local);
+                }
+                Some(Op::Relu(x)) => {
+                    let slope = if x.data() > F::ZERO { F::ONE } else { F::ZERO };
+                    accumulate(&mut grads, x, &grad * &Value::new(slope));
+                }
+                Some(Op::Custom { op, inputs }) => {
+                    let local = val.custom_op_input_grads(op.as_ref(), &inputs, F::ONE)?;
+                    for (input, local) in inputs.into_iter().zip(local) {
+                        accumulate(&mut grads, input, &grad * &Value::new(local));
+                    }
+                }
+                None => {}
+            }
+        }
+
+        Ok(inputs
+            .iter()
+            .map(|input| {
+                grads
+                    .get(input)
+                    .cloned()
+                    .unwrap_or_else(|| Value::new(F::ZERO))
+            })
+            .collect())
+    }
+
+    /// Computes the product of the Hessian of this value with respect to `inputs` and `vector`.
+    pub fn hessian_vector_product(&self, inputs: &[Value<F>], vector: &[F]) -> Vec<F> {
+        assert_eq!(inputs.len(), vector.len());
+
+        let grad_dot_vector = self
+            .gradients(inputs)
+            .iter()
+            .zip(vector)
+            .fold(Value::new(F::ZERO), |acc, (grad, v)| {
+                &acc + &(grad * &Value::new(*v))
+            });
+
+        grad_dot_vector
+            .gradients(inputs)
+            .iter()
+            .map(Value::data)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_derivative() {
+        let x = Value::new(2.0);
+        let y = &x.powf(3.0) + &(&x * &x).tanh();
+
+        let inputs = [x];
+        let [dy] = y.gradients(&inputs).try_into().unwrap();
+        let [ddy] = dy.gradients(&inputs).try_into().unwrap();
+
+        let t = 4.0f64.tanh();
+        let sech2 = 1.0 - t * t;
+        assert!((dy.data() - (12.0 + 4.0 * sech2)).abs() < 1e-12);
+        assert!((ddy.data() - (12.0 + 2.0 * sech2 - 8.0 * 4.0 * t * sech2)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_hessian_vector_product() {
+        // f(x, y) = x^2 y + y^3, Hessian = [[2y, 2x], [2x, 6y]]
+        let x = Value::new(3.0);
+        let y = Value::new(5.0);
+        let f = &(&x.powf(2.0) * &y) + &y.powf(3.0);
+
+        let hvp = f.hessian_vector_product(&[x.clone(), y.clone()], &[1.0, 2.0]);
+        assert_eq!(hvp, vec![10.0 + 12.0, 6.0 + 60.0]);
+
+        // The gradients of the nodes themselves are untouched
+        assert_eq!(x.grad(), 0.0);
+    }
+}
@@ -0,0 +1,67 @@
+use std::{cell::Cell, thread::LocalKey};
+
+thread_local! {
+    static ANOMALY_DETECTION: Cell<bool> = const { Cell::new(false) };
+    static GRAD_ENABLED: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Runs `f` with `flag` set to `value`, restoring the previous value afterwards (even on panic).
+fn with_flag<T>(flag: &'static LocalKey<Cell<bool>>, value: bool, f: impl FnOnce() -> T) -> T {
+    struct Guard(&'static LocalKey<Cell<bool>>, bool);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            self.0.set(self.1);
+        }
+    }
+
+    let _guard = Guard(flag, flag.replace(value));
+    f()
+}
+
+/// Runs `f` with anomaly detection enabled on the current thread. While enabled, `backward`
+/// checks the data and gradient of every node for NaN or infinite values and reports the
+/// operation and graph depth at which the first one was produced.
+pub fn detect_anomaly<T>(f: impl FnOnce() -> T) -> T {
+    with_flag(&ANOMALY_DETECTION, true, f)
+}
+
+pub(super) fn is_anomaly_detection_enabled() -> bool {
+    ANOMALY_DETECTION.get()
+}
+
+/// Runs `f` without recording the computation graph on the current thread. Operations on values
+/// produce leaves without any history, so nothing is kept alive for a later `backward`.
+pub fn no_grad<T>(f: impl FnOnce() -> T) -> T {
+    with_flag(&GRAD_ENABLED, false, f)
+}
+
+pub fn is_grad_enabled() -> bool {
+    GRAD_ENABLED.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+
+    #[test]
+    fn test_no_grad() {
+        let a = Value::new(3.0);
+        let b = Value::new(7.0);
+
+        let c = no_grad(|| {
+            let c = &a * &b;
+            assert!(!is_grad_enabled());
+            assert!(!no_grad(is_grad_enabled));
+            assert!(!is_grad_enabled());
+            c
+        });
+        assert!(is_grad_enabled());
+        assert_eq!(c.data(), 21.0);
+        assert_eq!(c.children().count(), 0);
+
+        let d = &a * &b;
+        assert_eq!(d.children().count(), 2);
+    }
+}
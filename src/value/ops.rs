@@ -1,45 +1,46 @@
 use super::{Op, Value};
+use crate::float::Float;
 
-impl std::ops::Add for &Value {
-    type Output = Value;
+impl<F: Float> std::ops::Add for &Value<F> {
+    type Output = Value<F>;
     fn add(self, rhs: Self) -> Self::Output {
         Value::with_op(self.data() + rhs.data(), Op::Add(self.clone(), rhs.clone()))
     }
 }
 
-impl std::ops::Mul for &Value {
-    type Output = Value;
+impl<F: Float> std::ops::Mul for &Value<F> {
+    type Output = Value<F>;
 
     fn mul(self, rhs: Self) -> Self::Output {
         Value::with_op(self.data() * rhs.data(), Op::Mul(self.clone(), rhs.clone()))
     }
 }
 
-impl std::ops::Neg for &Value {
-    type Output = Value;
+impl<F: Float> std::ops::Neg for &Value<F> {
+    type Output = Value<F>;
 
     fn neg(self) -> Self::Output {
-        self * &Value::new(-1.0)
+        self * &Value::new(-F::ONE)
     }
 }
 
-impl std::ops::Sub for &Value {
-    type Output = Value;
+impl<F: Float> std::ops::Sub for &Value<F> {
+    type Output = Value<F>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         self + &(-rhs)
     }
 }
 
-impl std::ops::Div for &Value {
-    type Output = Value;
+impl<F: Float> std::ops::Div for &Value<F> {
+    type Output = Value<F>;
     fn div(self, rhs: Self) -> Self::Output {
-        self * &rhs.powf(-1.0)
+        self * &rhs.powf(-F::ONE)
     }
 }
 
-impl Value {
-    pub fn powf(&self, exp: f64) -> Value {
+impl<F: Float> Value<F> {
+    pub fn powf(&self, exp: F) -> Self {
         Value::with_op(
             self.data().powf(exp),
             Op::Pow {
@@ -49,7 +50,17 @@ impl Value {
         )
     }
 
-    pub fn tanh(&self) -> Value {
+    pub fn tanh(&self) -> Self {
         Value::with_op(self.data().tanh(), Op::Tanh(self.clone()))
     }
+
+    pub fn relu(&self) -> Self {
+        Value::with_op(self.data().max(F::ZERO), Op::Relu(self.clone()))
+    }
+
+    pub fn sigmoid(&self) -> Self {
+        // sigmoid(x) = (1 + tanh(x / 2)) / 2
+        let half = Value::new(F::from_f64(0.5));
+        &(&Value::new(F::ONE) + &(self * &half).tanh()) * &half
+    }
 }
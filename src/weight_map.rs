@@ -0,0 +1,146 @@
+use std::io::{self, Write};
+
+use crate::{
+    error::{Error, Result},
+    float::Float,
+    module::Module,
+    multi_layer_perceptron::MultiLayerPerceptron,
+};
+
+/// The input weights of a single neuron arranged as an image, e.g. 28x28 for MNIST.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightMap {
+    pub width: usize,
+    pub height: usize,
+    /// Row-major weights.
+    pub weights: Vec<f64>,
+}
+
+impl WeightMap {
+    /// Scales the weights into [-1, 1] by the largest magnitude, so 0 stays 0.
+    pub fn normalized(&self) -> Vec<f64> {
+        let max = self.weights.iter().fold(0.0f64, |max, w| max.max(w.abs()));
+        let scale = if max > 0.0 { 1.0 / max } else { 0.0 };
+        self.weights.iter().map(|w| w * scale).collect()
+    }
+
+    /// Grayscale pixels with 0 as mid-gray, negative weights darker and positive ones lighter.
+    pub fn to_gray(&self) -> Vec<u8> {
+        self.normalized()
+            .iter()
+            .map(|w| ((w + 1.0) / 2.0 * 255.0).round() as u8)
+            .collect()
+    }
+
+    /// RGB pixels with a diverging colormap: blue for negative, white for 0 and red for positive
+    /// weights.
+    pub fn to_rgb(&self) -> Vec<u8> {
+        self.normalized()
+            .iter()
+            .flat_map(|&w| {
+                let fade = ((1.0 - w.abs()) * 255.0).round() as u8;
+                if w >= 0.0 {
+                    [255, fade, fade]
+                } else {
+                    [fade, fade, 255]
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the grayscale map as a binary PGM image.
+    pub fn write_pgm(&self, writer: impl Write) -> Result<()> {
+        let mut writer = io::BufWriter::new(writer);
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.to_gray())?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Writes the diverging colormap as a PNG image.
+    pub fn write_png(&self, writer: impl Write) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.to_rgb()))
+            .map_err(|err| match err {
+                png::EncodingError::IoError(err) => Error::Io(err),
+                err => Error::Io(io::Error::other(err)),
+            })
+    }
+}
+
+/// Returns the weight map of every neuron in the first layer of `model`. Returns an error if the
+/// number of inputs is not `width * height`.
+pub fn first_layer_weight_maps<F: Float>(
+    model: &MultiLayerPerceptron<F>,
+    width: usize,
+    height: usize,
+) -> Result<Vec<WeightMap>> {
+    let layer_sizes = model.layer_sizes();
+    let (num_inputs, num_neurons) = (layer_sizes[0], layer_sizes[1]);
+    if num_inputs != width * height {
+        return Err(Error::DimensionMismatch {
+            expected: width * height,
+            actual: num_inputs,
+        });
+    }
+
+    // Each neuron's weights are followed by its bias, see `Neuron::parameters`
+    let mut parameters = model.parameters().map(|p| p.data().to_f64());
+    Ok((0..num_neurons)
+        .map(|_| {
+            let weights = parameters.by_ref().take(num_inputs).collect();
+            parameters.next();
+            WeightMap {
+                width,
+                height,
+                weights,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let model: MultiLayerPerceptron = MultiLayerPerceptron::new(6, &[4], 2);
+        let maps = first_layer_weight_maps(&model, 3, 2).unwrap();
+        assert_eq!(maps.len(), 4);
+
+        // The second map starts after the weights and bias of the first neuron
+        let parameters = model.parameters().map(|p| p.data()).collect::<Vec<_>>();
+        assert_eq!(maps[1].weights, parameters[7..13]);
+
+        let map = WeightMap {
+            width: 3,
+            height: 1,
+            weights: vec![-2.0, 0.0, 1.0],
+        };
+        assert_eq!(map.to_gray(), [0, 128, 191]);
+        assert_eq!(map.to_rgb(), [0, 0, 255, 255, 255, 255, 255, 128, 128]);
+
+        let mut pgm = Vec::new();
+        map.write_pgm(&mut pgm).unwrap();
+        assert_eq!(pgm, b"P5\n3 1\n255\n\x00\x80\xbf");
+
+        let mut png = Vec::new();
+        map.write_png(&mut png).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+
+        assert!(matches!(
+            first_layer_weight_maps(&model, 2, 2),
+            Err(Error::DimensionMismatch {
+                expected: 4,
+                actual: 6
+            })
+        ));
+    }
+}